@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use q::WatchEventKind;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let c = q::Q::new();
+
+    let mut events = c
+        .watch("1131", ["CS1003302", "TCG046301"])?
+        .interval(Duration::from_secs(5))
+        .stream();
+
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(event) if event.kind == WatchEventKind::SeatOpened => println!(
+                "{} has a free seat: {}/{}",
+                event.after.course_no,
+                event.after.choose_student,
+                event.after.limit()
+            ),
+            Ok(event) => println!("{}: {:?}", event.after.course_no, event.kind),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    Ok(())
+}
@@ -36,10 +36,17 @@ async fn main() -> anyhow::Result<()> {
 
     let pool = Pool::bounded(THREADS).with_spawn_timeout(Duration::from_micros(10));
 
+    // all clients share one budget of 20 requests per second
+    let limiter = Arc::new(q::RateLimiter::new(20.0, 10));
+
     let mut clients = Vec::<q::Q>::new();
 
     for _ in 0..(THREADS + 1) {
-        clients.push(q::Q::new());
+        clients.push(
+            q::ClientBuilder::new()
+                .rate_limiter(limiter.clone())
+                .build(),
+        );
     }
 
     let clients = Arc::new(clients);
@@ -0,0 +1,82 @@
+use crate::{BilingualCourse, Course, CourseNo, Language, QueryError, SearchOptions, Semester};
+
+use super::Q;
+
+impl Q {
+    /// Fetch a course in both Chinese and English.
+    ///
+    /// Besides the details, each language is also searched for the course, since
+    /// `contents` and the time slots only come with search results.
+    pub async fn query_bilingual<S, C>(
+        &self,
+        semester: S,
+        course_no: C,
+    ) -> Result<BilingualCourse, QueryError>
+    where
+        S: TryInto<Semester>,
+        C: TryInto<CourseNo>,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
+        let semester = semester.try_into()?;
+        let course_no = course_no.try_into()?;
+
+        let (zh, en) = futures::try_join!(
+            self.query_course(semester, &course_no, Language::Zh),
+            self.query_course(semester, &course_no, Language::En),
+        )?;
+
+        BilingualCourse::new(zh, en)
+    }
+
+    /// Details of the course joined with its search hit, if the search finds it.
+    async fn query_course(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> Result<Course, QueryError> {
+        let mut options = SearchOptions::new(semester, language);
+        options.course_no = course_no.to_string();
+
+        let (infos, details) = futures::try_join!(
+            self.search(&options, true),
+            self.query(semester, course_no, language),
+        )?;
+
+        match infos
+            .into_iter()
+            .find(|c| c.course_no == course_no.as_str())
+        {
+            Some(info) => Course::from_parts(info, details),
+            None => Course::try_from(details),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::MockServer, ClientBuilder};
+
+    #[tokio::test]
+    async fn query_bilingual() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        let course = client.query_bilingual("1131", "CS1003302").await.unwrap();
+        assert_eq!(course.course_name.zh, "計算機程式設計");
+        assert_eq!(course.course_name.en, "Computer Programming");
+        assert_eq!(course.course_teacher.en, "Tai-Lin Chin");
+        assert_eq!(course.course.node.as_deref(), Some("M3,M4,R6"));
+        assert_ne!(
+            course.syllabus.zh.as_ref().unwrap().course_object,
+            course.syllabus.en.as_ref().unwrap().course_object
+        );
+        assert_eq!(server.hits(), 4);
+
+        let e = client
+            .query_bilingual("1131", "EE1003302")
+            .await
+            .unwrap_err();
+        assert!(e.is_not_found());
+    }
+}
@@ -0,0 +1,186 @@
+use futures::{stream, stream::BoxStream, StreamExt};
+
+use crate::{
+    Course, CourseDetails, CourseNo, DetailedSearch, Language, QueryError, SearchOptions, Semester,
+};
+
+use super::Q;
+
+/// A reasonable `concurrency` for `Q::query_many` that stays gentle on the API.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+impl Q {
+    /// Query many courses through this client, at most `concurrency` at a time.
+    ///
+    /// Results are yielded in completion order, each with the index of its course in `courses`.
+    /// Invalid input fails only its own item.
+    pub fn query_many<I, S, C>(
+        &self,
+        courses: I,
+        language: Language,
+        concurrency: usize,
+    ) -> BoxStream<'_, (usize, Result<CourseDetails, QueryError>)>
+    where
+        I: IntoIterator<Item = (S, C)>,
+        S: TryInto<Semester>,
+        C: TryInto<CourseNo>,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
+        let courses: Vec<Result<(Semester, CourseNo), QueryError>> = courses
+            .into_iter()
+            .map(|(semester, course_no)| Ok((semester.try_into()?, course_no.try_into()?)))
+            .collect();
+
+        stream::iter(courses.into_iter().enumerate())
+            .map(move |(index, course)| async move {
+                let result = match course {
+                    Ok((semester, course_no)) => self.query(semester, course_no, language).await,
+                    Err(e) => Err(e),
+                };
+                (index, result)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .boxed()
+    }
+
+    /// Like `query_many`, but wait for every result and return them in input order.
+    pub async fn query_many_ordered<I, S, C>(
+        &self,
+        courses: I,
+        language: Language,
+        concurrency: usize,
+    ) -> Vec<Result<CourseDetails, QueryError>>
+    where
+        I: IntoIterator<Item = (S, C)>,
+        S: TryInto<Semester>,
+        C: TryInto<CourseNo>,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
+        let mut results: Vec<_> = self
+            .query_many(courses, language, concurrency)
+            .collect()
+            .await;
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Search, then query the details of every hit, at most `concurrency` at a time.
+    ///
+    /// Hits are merged by course number. Only a failed search is an error,
+    /// failed detail queries are collected in `DetailedSearch::failures`.
+    pub async fn search_detailed(
+        &self,
+        options: &SearchOptions,
+        concurrency: usize,
+    ) -> Result<DetailedSearch, QueryError> {
+        let infos = self.search(options, true).await?;
+
+        let results = self
+            .query_many_ordered(
+                infos
+                    .iter()
+                    .map(|c| (c.semester.as_str(), c.course_no.as_str())),
+                options.language,
+                concurrency,
+            )
+            .await;
+
+        let mut detailed = DetailedSearch::default();
+        for (info, result) in infos.into_iter().zip(results) {
+            match result.and_then(|details| Course::from_parts(info.clone(), details)) {
+                Ok(course) => detailed.courses.push(course),
+                Err(e) => detailed.failures.push((info, e)),
+            }
+        }
+
+        Ok(detailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        testing::{Fault, MockServer},
+        ClientBuilder,
+    };
+
+    #[tokio::test]
+    async fn ordered() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        let results = client
+            .query_many_ordered(
+                [
+                    ("1131", "CS2006301"),
+                    ("1131", "cs"),
+                    ("1131", "CS1003302"),
+                    ("1131", "EE1003302"),
+                ],
+                Language::Zh,
+                2,
+            )
+            .await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().course_no, "CS2006301");
+        assert!(results[1].as_ref().unwrap_err().is_input());
+        assert_eq!(results[2].as_ref().unwrap().course_no, "CS1003302");
+        assert!(results[3].as_ref().unwrap_err().is_not_found());
+
+        // the invalid course number never reaches the server
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn search_detailed() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        let mut options = SearchOptions::new("1131", Language::En);
+        options.course_no = String::from("CS");
+
+        // the search and the details of CS1003302 go through
+        server.inject_for("CS2006301", Fault::Status(500));
+        let detailed = client.search_detailed(&options, 2).await.unwrap();
+
+        assert!(!detailed.is_complete());
+        assert_eq!(detailed.courses.len(), 1);
+        assert_eq!(detailed.courses[0].course_no.as_str(), "CS1003302");
+        assert_eq!(detailed.failures.len(), 1);
+        assert_eq!(
+            detailed.failures[0].1.status().map(|s| s.as_u16()),
+            Some(500)
+        );
+
+        let course = &detailed.courses[0];
+        assert!(course.node.is_some());
+        assert!(course.syllabus.as_ref().unwrap().course_object.is_some());
+
+        let detailed = client.search_detailed(&options, 4).await.unwrap();
+        assert!(detailed.is_complete());
+        assert_eq!(detailed.courses.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn bounded() {
+        let server = MockServer::start();
+        server.set_latency(Duration::from_millis(100));
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        let courses = ["CS1003302", "CS2006301", "GE3701301", "CS1003302"];
+        let started = std::time::Instant::now();
+        let results: Vec<_> = client
+            .query_many(courses.map(|c| ("1131", c)), Language::Zh, 2)
+            .collect()
+            .await;
+
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|(_, r)| r.is_ok()));
+        // two rounds of two requests each
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+}
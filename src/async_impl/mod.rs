@@ -1,23 +1,53 @@
-use std::collections::HashMap;
+use std::{sync::Arc, time::Duration};
 
+use bytes::Bytes;
+use reqwest::StatusCode;
 use url::Url;
 
 use crate::{
-    default_reqwest_builder, CourseDetails, CourseInfo, Language, QueryError, SearchOptions,
-    DEFAULT_API_URL,
+    cache::{Cache, DEFAULT_CACHE_TTL},
+    cassette::{Cassette, Interaction, Mode},
+    default_reqwest_builder, CourseDetails, CourseInfo, CourseNo, Language, QueryError,
+    SearchOptions, Semester, DEFAULT_API_URL,
 };
 
+mod bilingual;
+mod bulk;
+pub use self::bulk::DEFAULT_CONCURRENCY;
+mod poll;
+pub use self::poll::MIN_POLL_INTERVAL;
+mod rate_limit;
+pub use self::rate_limit::RateLimiter;
+mod recorder;
+pub use self::recorder::{Recorder, DEFAULT_RECORD_INTERVAL};
+mod retry;
+pub use self::retry::RetryPolicy;
+mod watcher;
+pub use self::watcher::{WatchEvent, WatchEventKind, Watcher, DEFAULT_WATCH_INTERVAL};
+
 #[derive(Debug)]
 pub struct ClientBuilder {
     reqwest_client: reqwest::Client,
     base_url: Url,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    cache: Option<Arc<dyn Cache>>,
+    search_cache_ttl: Duration,
+    query_cache_ttl: Duration,
+    cassette: Option<Arc<Cassette>>,
 }
 
 impl ClientBuilder {
     pub fn new() -> Self {
         ClientBuilder {
             reqwest_client: default_reqwest_builder().build().unwrap(),
-            base_url: Url::parse(&DEFAULT_API_URL).unwrap(),
+            base_url: Url::parse(DEFAULT_API_URL).unwrap(),
+            retry_policy: RetryPolicy::none(),
+            rate_limiter: None,
+            cache: None,
+            search_cache_ttl: DEFAULT_CACHE_TTL,
+            query_cache_ttl: DEFAULT_CACHE_TTL,
+            cassette: None,
         }
     }
 
@@ -31,10 +61,64 @@ impl ClientBuilder {
         self
     }
 
+    /// Retry policy applied to both `search` and `query`, no retries by default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Rate limit every request, including retries, pass the same `Arc` to
+    /// several builders to share one budget between clients.
+    pub fn rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Shorthand for a rate limiter owned by this client alone.
+    ///
+    /// # Panics
+    ///
+    /// If `requests_per_second` is not positive, see `RateLimiter::new`.
+    pub fn rate_limit(self, requests_per_second: f64, burst: u32) -> Self {
+        self.rate_limiter(Arc::new(RateLimiter::new(requests_per_second, burst)))
+    }
+
+    /// Cache successful responses, e.g. in a `cache::MemoryCache` or `cache::DiskCache`.
+    pub fn cache(mut self, cache: Arc<dyn Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// How long `search` responses are cached, a zero TTL disables caching them.
+    pub fn search_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.search_cache_ttl = ttl;
+        self
+    }
+
+    /// How long `query` responses are cached, a zero TTL disables caching them.
+    pub fn query_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.query_cache_ttl = ttl;
+        self
+    }
+
+    /// Record every response to, or replay them from, a cassette file.
+    ///
+    /// Recording sits below the cache, so only responses fetched from the server are saved.
+    pub fn cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
     pub fn build(self) -> Q {
         Q {
             http_client: self.reqwest_client,
             base_url: self.base_url,
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            cache: self.cache,
+            search_cache_ttl: self.search_cache_ttl,
+            query_cache_ttl: self.query_cache_ttl,
+            cassette: self.cassette,
         }
     }
 }
@@ -42,6 +126,12 @@ impl ClientBuilder {
 pub struct Q {
     http_client: reqwest::Client,
     base_url: Url,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    cache: Option<Arc<dyn Cache>>,
+    search_cache_ttl: Duration,
+    query_cache_ttl: Duration,
+    cassette: Option<Arc<Cassette>>,
 }
 
 impl Q {
@@ -54,72 +144,227 @@ impl Q {
         options: &SearchOptions,
         merge_courses: bool,
     ) -> Result<Vec<CourseInfo>, QueryError> {
+        options.semester.parse::<Semester>()?;
+
         let url = self.base_url.join("courses").unwrap();
 
-        let resp = match self.http_client.post(url).json(&options).send().await {
-            Ok(resp) => resp,
-            Err(e) => return Err(QueryError::HttpError(format!("{}", e))),
-        };
+        let request = self.http_client.post(url).json(&options).build()?;
 
-        match resp.json::<Vec<CourseInfo>>().await {
-            Ok(json) => Ok(if merge_courses {
-                crate::merge_courses(json)
-            } else {
-                json
-            }),
-            Err(e) => return Err(QueryError::ParseError(format!("{}", e))),
-        }
+        let json = self
+            .fetch_parsed(request, self.search_cache_ttl, |full| {
+                crate::from_json::<Vec<CourseInfo>>(full)
+            })
+            .await?;
+
+        Ok(if merge_courses {
+            crate::merge_courses(json)
+        } else {
+            json
+        })
     }
 
-    pub async fn query(
+    pub async fn query<S, C>(
         &self,
-        semester: &str,
-        course_no: &str,
+        semester: S,
+        course_no: C,
         language: Language,
-    ) -> Result<CourseDetails, QueryError> {
+    ) -> Result<CourseDetails, QueryError>
+    where
+        S: TryInto<Semester>,
+        C: TryInto<CourseNo>,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
+        let semester = semester.try_into()?.to_string();
+        let course_no = course_no.try_into()?;
+
         // is "coursedetials" not "coursedetails"
         // looks like an idiotic typo in the API
         let url = self.base_url.join("coursedetials").unwrap();
 
-        let mut params = HashMap::new();
-        params.insert("semester", semester);
-        params.insert("course_no", course_no);
-        params.insert("language", language.as_str());
+        // in a fixed order so that equal queries share a cache key
+        let params = [
+            ("semester", semester.as_str()),
+            ("course_no", course_no.as_str()),
+            ("language", language.as_str()),
+        ];
 
-        let resp = match self.http_client.get(url).query(&params).send().await {
-            Ok(resp) => resp,
-            Err(e) => return Err(QueryError::HttpError(format!("{}", e))),
-        };
+        let request = self.http_client.get(url).query(&params).build()?;
 
-        let full = match resp.bytes().await {
-            Ok(bytes) => bytes,
-            Err(e) => return Err(QueryError::ParseError(format!("{}", e))),
-        };
+        self.fetch_parsed(request, self.query_cache_ttl, |full| {
+            // # debug
+            // println!("{}", String::from_utf8_lossy(full));
+
+            let json = crate::from_json::<Vec<CourseDetails>>(full)?;
+
+            json.into_iter().next().ok_or(QueryError::NotFoundError)
+        })
+        .await
+    }
+
+    /// Fetch and parse the response, going through the cache if one is configured.
+    ///
+    /// Only bodies that parse successfully are cached.
+    async fn fetch_parsed<T, F>(
+        &self,
+        request: reqwest::Request,
+        ttl: Duration,
+        parse: F,
+    ) -> Result<T, QueryError>
+    where
+        F: Fn(&[u8]) -> Result<T, QueryError>,
+    {
+        let cache = self.cache.as_ref().filter(|_| !ttl.is_zero());
+        let key = cache.map(|_| cache_key(&request));
+
+        if let (Some(cache), Some(key)) = (cache, &key) {
+            if let Some(value) = cache.get(key).and_then(|full| parse(&full).ok()) {
+                return Ok(value);
+            }
+        }
+
+        let full = self.fetch(request).await?;
+        let value = parse(&full)?;
 
-        // # debug
-        // let text = match String::from_utf8(full.to_vec()) {
-        //     Ok(bytes) => bytes,
-        //     Err(e) => return Err(QueryError::ParseError(format!("{}", e))),
-        // };
-        // println!("{}", text);
+        if let (Some(cache), Some(key)) = (cache, &key) {
+            cache.put(key, full, ttl);
+        }
+
+        Ok(value)
+    }
 
-        let json = match serde_json::from_slice::<Vec<CourseDetails>>(&full) {
-            Ok(json) => json,
-            Err(e) => return Err(QueryError::ParseError(format!("{}", e))),
+    /// Send the request and read the full body, retrying according to the retry policy.
+    async fn fetch(&self, request: reqwest::Request) -> Result<Bytes, QueryError> {
+        let mut attempt = 1;
+
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+
+            // requests built by `search` and `query` always have a cloneable body
+            let result = self.fetch_once(request.try_clone().unwrap()).await;
+
+            match result {
+                Err(e)
+                    if self.retry_policy.can_retry(attempt)
+                        && self.retry_policy.is_retryable(&e) =>
+                {
+                    tokio::time::sleep(self.retry_policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn fetch_once(&self, request: reqwest::Request) -> Result<Bytes, QueryError> {
+        let (status, body) = match &self.cassette {
+            Some(cassette) => self.fetch_cassette(cassette, request).await?,
+            None => {
+                let resp = self.http_client.execute(request).await?;
+                (resp.status(), resp.bytes().await?)
+            }
         };
 
-        if let Some(course_details) = json.get(0) {
-            Ok(course_details.clone())
+        if status.is_success() {
+            Ok(body)
         } else {
-            Err(QueryError::ParseError(format!("No course found")))
+            Err(QueryError::status_error(status, &body))
+        }
+    }
+}
+
+impl Q {
+    async fn fetch_cassette(
+        &self,
+        cassette: &Cassette,
+        request: reqwest::Request,
+    ) -> Result<(StatusCode, Bytes), QueryError> {
+        let method = request.method().to_string();
+        let url = self
+            .base_url
+            .make_relative(request.url())
+            .unwrap_or_else(|| request.url().to_string());
+        let request_body = request_body(&request).into_owned();
+
+        match cassette.mode() {
+            Mode::Replay => {
+                let (status, body) =
+                    cassette.play(&method, &url, &request_body).ok_or_else(|| {
+                        QueryError::ReplayError(format!(
+                            "no recorded response for {} {}",
+                            method, url
+                        ))
+                    })?;
+                let status = StatusCode::from_u16(status).map_err(|_| {
+                    QueryError::ReplayError(format!("invalid recorded status {}", status))
+                })?;
+                Ok((status, body))
+            }
+            Mode::Record => {
+                let resp = self.http_client.execute(request).await?;
+                let status = resp.status();
+                let body = resp.bytes().await?;
+
+                cassette.append(Interaction {
+                    method,
+                    url,
+                    request_body,
+                    status: status.as_u16(),
+                    response_body: String::from_utf8_lossy(&body).into_owned(),
+                });
+
+                Ok((status, body))
+            }
         }
     }
 }
 
+fn request_body(request: &reqwest::Request) -> std::borrow::Cow<'_, str> {
+    request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default()
+}
+
+fn cache_key(request: &reqwest::Request) -> String {
+    format!(
+        "{} {} {}",
+        request.method(),
+        request.url(),
+        request_body(request)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use futures::future::join_all;
+    use crate::testing::MockServer;
+    use futures::StreamExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn not_found() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+        let result = client.query("1131", "EE1003302", Language::Zh).await;
+
+        assert!(result.unwrap_err().is_not_found());
+    }
+
+    #[tokio::test]
+    async fn connection_error() {
+        // nothing is listening on the port once the listener is dropped
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/api/", listener.local_addr().unwrap())).unwrap();
+        drop(listener);
+
+        let client = ClientBuilder::new().api_url(url).build();
+        let result = client.query("1122", "AT2005701", Language::Zh).await;
+
+        assert!(result.unwrap_err().is_connection());
+    }
 
     #[tokio::test]
     async fn new() {
@@ -128,36 +373,55 @@ mod tests {
 
     #[tokio::test]
     async fn search() {
-        let client = Q::new();
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
 
         let mut options = SearchOptions::new("1131", Language::Zh);
 
         options.course_no = "cs".to_string();
 
-        let _details = client
+        let courses = client
             .search(&options, true)
             .await
             .expect("failed to search courses");
 
-        println!("{:#?}", _details);
+        assert!(!courses.is_empty());
+        assert!(courses.iter().all(|c| c.course_no.starts_with("CS")));
     }
 
     #[tokio::test]
     async fn query() {
-        let client = Q::new();
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
 
-        let _details = client
-            .query("1122", "AT2005701", Language::Zh)
+        let details = client
+            .query("1131", "CS1003302", Language::Zh)
             .await
             .expect("Failed to query");
 
-        println!("{:#?}", _details)
+        assert_eq!(details.course_no, "CS1003302");
     }
 
     #[tokio::test]
-    async fn query_all_cs() {
+    async fn invalid_input() {
         let client = Q::new();
 
+        let result = client.query("1134", "AT2005701", Language::Zh).await;
+        assert!(matches!(result, Err(QueryError::InputError(_))));
+
+        let result = client.query("1122", "AT20057", Language::Zh).await;
+        assert!(matches!(result, Err(QueryError::InputError(_))));
+
+        let options = SearchOptions::new("11x1", Language::Zh);
+        let result = client.search(&options, true).await;
+        assert!(matches!(result, Err(QueryError::InputError(_))));
+    }
+
+    #[tokio::test]
+    async fn query_all_cs() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
         let mut options = SearchOptions::new("1131", Language::Zh);
 
         options.course_no = "cs".to_string();
@@ -167,16 +431,15 @@ mod tests {
             .await
             .expect("failed to search courses");
 
-        let futures = search_results.into_iter().map(|c| async move {
-            let query_client = Q::new();
-            let details = query_client
-                .query(&c.semester, &c.course_no, Language::Zh)
-                .await
-                .expect("Failed to query");
+        let courses = search_results
+            .iter()
+            .map(|c| (c.semester.as_str(), c.course_no.as_str()));
 
-            println!("{:#?}", details);
-        });
+        let mut results = client.query_many(courses, Language::Zh, DEFAULT_CONCURRENCY);
+        while let Some((_, details)) = results.next().await {
+            let details = details.expect("Failed to query");
 
-        join_all(futures).await;
+            assert!(details.course_no.starts_with("CS"));
+        }
     }
 }
@@ -0,0 +1,34 @@
+use std::{collections::VecDeque, future::Future, time::Duration};
+
+use futures::{stream, stream::BoxStream, StreamExt};
+use tokio::time::{interval, MissedTickBehavior};
+
+/// Shortest interval between two polls, shorter intervals are raised to it.
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// One round of a periodic poll, see `poll_stream`.
+pub(super) trait Poll: Send {
+    type Item: Send;
+
+    fn poll(&mut self) -> impl Future<Output = Vec<Self::Item>> + Send;
+}
+
+/// Call `poller` every `period`, yielding the items of each round in order.
+///
+/// The first round starts immediately, a round that overruns delays the next one.
+pub(super) fn poll_stream<'a, P: Poll + 'a>(poller: P, period: Duration) -> BoxStream<'a, P::Item> {
+    let mut ticker = interval(period.max(MIN_POLL_INTERVAL));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let state = (poller, ticker, VecDeque::new());
+    stream::unfold(state, |(mut poller, mut ticker, mut pending)| async move {
+        loop {
+            if let Some(item) = pending.pop_front() {
+                return Some((item, (poller, ticker, pending)));
+            }
+            ticker.tick().await;
+            pending.extend(poller.poll().await);
+        }
+    })
+    .boxed()
+}
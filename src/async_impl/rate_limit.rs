@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use tokio::{sync::Mutex, time::Instant};
+
+/// Token bucket rate limiter.
+///
+/// Tokens refill continuously at `requests_per_second` up to `burst`,
+/// every request sent by `Q` takes one token and waits if none are left.
+/// Wrap it in an `Arc` to share one budget between several clients.
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// `burst` is the number of requests that may be sent at once, a `burst` of `0` is raised to `1`.
+    ///
+    /// # Panics
+    ///
+    /// If `requests_per_second` is not positive, e.g. zero, negative or NaN.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        assert!(
+            requests_per_second > 0.0,
+            "requests_per_second must be positive"
+        );
+
+        let burst = burst.max(1) as f64;
+
+        RateLimiter {
+            requests_per_second,
+            burst,
+            state: Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until a token is available and take it.
+    pub async fn acquire(&self) {
+        // holding the lock while sleeping keeps waiters in FIFO order
+        let mut bucket = self.state.lock().await;
+
+        self.refill(&mut bucket);
+
+        if bucket.tokens < 1.0 {
+            // a tiny rate may need a wait longer than `Duration` can hold
+            let wait =
+                Duration::try_from_secs_f64((1.0 - bucket.tokens) / self.requests_per_second)
+                    .unwrap_or(Duration::MAX);
+            tokio::time::sleep(wait).await;
+            self.refill(&mut bucket);
+        }
+
+        bucket.tokens -= 1.0;
+    }
+
+    /// Take a token if one is available right now.
+    pub fn try_acquire(&self) -> bool {
+        let Ok(mut bucket) = self.state.try_lock() else {
+            return false;
+        };
+
+        self.refill(&mut bucket);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+        bucket.last_refill = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::{testing::MockServer, ClientBuilder, Language, SearchOptions};
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_then_rate() {
+        let limiter = RateLimiter::new(10.0, 3);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert!(!limiter.try_acquire());
+
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(499), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(520), "{:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shared() {
+        let limiter = Arc::new(RateLimiter::new(2.0, 1));
+        let start = Instant::now();
+
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move { limiter.acquire().await })
+            })
+            .collect();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(1499), "{:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tiny_rate() {
+        let limiter = RateLimiter::new(f64::MIN_POSITIVE, 1);
+        limiter.acquire().await;
+
+        let waited = tokio::time::timeout(Duration::from_secs(3600), limiter.acquire()).await;
+        assert!(waited.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "requests_per_second must be positive")]
+    fn nan_rate() {
+        RateLimiter::new(f64::NAN, 1);
+    }
+
+    #[tokio::test]
+    async fn shared_between_clients() {
+        let server = MockServer::start();
+        let limiter = Arc::new(RateLimiter::new(20.0, 1));
+        let a = ClientBuilder::new()
+            .api_url(server.url())
+            .rate_limiter(limiter.clone())
+            .build();
+        let b = ClientBuilder::new()
+            .api_url(server.url())
+            .rate_limiter(limiter)
+            .build();
+
+        let options = SearchOptions::new("1131", Language::Zh);
+        let start = Instant::now();
+        a.search(&options, true).await.unwrap();
+        b.search(&options, true).await.unwrap();
+        a.search(&options, true).await.unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(90));
+        assert_eq!(server.hits(), 3);
+    }
+}
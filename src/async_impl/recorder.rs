@@ -0,0 +1,167 @@
+use std::{time::Duration, time::SystemTime};
+
+use futures::{future::join_all, stream::BoxStream};
+
+use crate::{
+    enrollment::{Sample, SampleStore},
+    CourseNo, Language, QueryError, SearchOptions, Semester,
+};
+
+use super::{
+    poll::{poll_stream, Poll, MIN_POLL_INTERVAL},
+    Q,
+};
+
+/// Default time between two rounds of a `Recorder`.
+pub const DEFAULT_RECORD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Samples enrollment counts of a list of courses into a `SampleStore`, created by `Q::record`.
+pub struct Recorder<'a, T> {
+    client: &'a Q,
+    semester: Semester,
+    course_nos: Vec<CourseNo>,
+    store: T,
+    interval: Duration,
+}
+
+impl<'a, T: SampleStore + 'a> Recorder<'a, T> {
+    /// Time between two rounds, at least `MIN_POLL_INTERVAL`.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval.max(MIN_POLL_INTERVAL);
+        self
+    }
+
+    pub fn store(&self) -> &T {
+        &self.store
+    }
+
+    /// Sample every course once and append the samples to the store.
+    ///
+    /// Each course is queried for its counts and searched for its NTU, NTNU and abroad counts.
+    /// If the store fails, every sample of the round is reported with its error.
+    pub async fn sample(&self) -> Vec<Result<Sample, QueryError>> {
+        let mut results = join_all(
+            self.course_nos
+                .iter()
+                .map(|course_no| self.sample_course(course_no)),
+        )
+        .await;
+
+        let samples: Vec<Sample> = results.iter().flatten().cloned().collect();
+        if let Err(e) = self.store.append(&samples) {
+            for result in results.iter_mut().filter(|r| r.is_ok()) {
+                *result = Err(e.clone());
+            }
+        }
+
+        results
+    }
+
+    async fn sample_course(&self, course_no: &CourseNo) -> Result<Sample, QueryError> {
+        let mut options = SearchOptions::new(self.semester, Language::Zh);
+        options.course_no = course_no.to_string();
+
+        let (infos, details) = futures::try_join!(
+            self.client.search(&options, true),
+            self.client.query(self.semester, course_no, Language::Zh),
+        )?;
+        let info = infos.iter().find(|c| c.course_no == course_no.as_str());
+
+        Ok(Sample::new(&details, info, SystemTime::now()))
+    }
+
+    /// Sample every `interval` until the stream is dropped, yielding each sample once it is stored.
+    pub fn stream(self) -> BoxStream<'a, Result<Sample, QueryError>> {
+        let interval = self.interval;
+        poll_stream(self, interval)
+    }
+}
+
+impl<T: SampleStore> Poll for Recorder<'_, T> {
+    type Item = Result<Sample, QueryError>;
+
+    async fn poll(&mut self) -> Vec<Self::Item> {
+        self.sample().await
+    }
+}
+
+impl Q {
+    /// Record the enrollment counts of the given courses into `store`.
+    pub fn record<S, I, C, T>(
+        &self,
+        semester: S,
+        course_nos: I,
+        store: T,
+    ) -> Result<Recorder<'_, T>, QueryError>
+    where
+        S: TryInto<Semester>,
+        I: IntoIterator<Item = C>,
+        C: TryInto<CourseNo>,
+        T: SampleStore,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
+        let semester = semester.try_into()?;
+        let course_nos = course_nos
+            .into_iter()
+            .map(|c| c.try_into().map_err(QueryError::from))
+            .collect::<Result<Vec<CourseNo>, QueryError>>()?;
+
+        Ok(Recorder {
+            client: self,
+            semester,
+            course_nos,
+            store,
+            interval: DEFAULT_RECORD_INTERVAL,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{enrollment::MemorySamples, testing::MockServer, ClientBuilder};
+
+    #[tokio::test]
+    async fn record() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+        let store = Arc::new(MemorySamples::new());
+
+        let recorder = client
+            .record("1131", ["CS1003302", "EE1003302"], store.clone())
+            .unwrap()
+            .interval(Duration::from_millis(10));
+
+        let results = recorder.sample().await;
+        let sample = results[0].as_ref().unwrap();
+        assert_eq!(sample.course_no, "CS1003302");
+        assert!(sample.choose_student > 0);
+        assert!(sample.ntu_people.is_some());
+        assert!(results[1].as_ref().unwrap_err().is_not_found());
+        assert_eq!(store.samples().unwrap().len(), 1);
+
+        let samples: Vec<_> = recorder.stream().take(4).collect().await;
+        assert_eq!(samples.iter().filter(|r| r.is_ok()).count(), 2);
+        assert_eq!(store.samples().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn zero_interval() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        let samples: Vec<_> = client
+            .record("1131", ["CS1003302"], MemorySamples::new())
+            .unwrap()
+            .interval(Duration::ZERO)
+            .stream()
+            .take(2)
+            .collect()
+            .await;
+        assert!(samples.iter().all(|r| r.is_ok()));
+    }
+}
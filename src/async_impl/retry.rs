@@ -0,0 +1,256 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use reqwest::StatusCode;
+
+use crate::QueryError;
+
+/// How failed requests are retried.
+///
+/// The delay before retry `n` (starting from 1) is
+/// `initial_backoff * multiplier^(n - 1)`, capped at `max_backoff`,
+/// then reduced by a random fraction of up to `jitter`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    retry_on_timeout: bool,
+    retry_on_connect: bool,
+    retry_statuses: Vec<StatusCode>,
+}
+
+impl RetryPolicy {
+    /// Three attempts with backoff starting at 200ms, retrying timeouts, connection
+    /// errors and `408`, `429`, `500`, `502`, `503`, `504`.
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.5,
+            retry_on_timeout: true,
+            retry_on_connect: true,
+            retry_statuses: vec![
+                StatusCode::REQUEST_TIMEOUT,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+
+    /// Never retry, every request is attempted exactly once.
+    pub fn none() -> Self {
+        Self::new().max_attempts(1)
+    }
+
+    /// Total number of attempts including the first one, at least `1`.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Fraction of the delay that may be randomly removed, between `0.0` and `1.0`.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn retry_on_timeout(mut self, retry: bool) -> Self {
+        self.retry_on_timeout = retry;
+        self
+    }
+
+    pub fn retry_on_connect(mut self, retry: bool) -> Self {
+        self.retry_on_connect = retry;
+        self
+    }
+
+    /// Replace the list of HTTP status codes that are retried.
+    pub fn retry_statuses(mut self, statuses: Vec<StatusCode>) -> Self {
+        self.retry_statuses = statuses;
+        self
+    }
+
+    /// Whether another attempt is allowed after `attempt` attempts have been made.
+    pub(crate) fn can_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+
+    /// Whether the error is worth another attempt under this policy.
+    pub fn is_retryable(&self, e: &QueryError) -> bool {
+        match e {
+            QueryError::TimeoutError(_) => self.retry_on_timeout,
+            QueryError::ConnectionError(_) => self.retry_on_connect,
+            QueryError::StatusError { status, .. } => self.retry_statuses.contains(status),
+            _ => false,
+        }
+    }
+
+    /// Delay before the given retry, `retry` starts from 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = self
+            .multiplier
+            .powi(retry.saturating_sub(1).min(i32::MAX as u32) as i32);
+        let delay = self
+            .initial_backoff
+            .mul_f64(exp.min(u32::MAX as f64))
+            .min(self.max_backoff);
+
+        if self.jitter > 0.0 {
+            delay.mul_f64(1.0 - self.jitter * random_fraction())
+        } else {
+            delay
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// a random number in [0, 1), good enough for jitter
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64,
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{Fault, MockServer},
+        ClientBuilder, Language, SearchOptions, Q,
+    };
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(500))
+            .multiplier(2.0)
+            .jitter(0.0);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .jitter(0.5);
+
+        for _ in 0..100 {
+            let delay = policy.backoff(1);
+            assert!(delay <= Duration::from_millis(100));
+            assert!(delay >= Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn statuses() {
+        let unavailable = QueryError::status_error(StatusCode::SERVICE_UNAVAILABLE, b"");
+        let not_found = QueryError::status_error(StatusCode::NOT_FOUND, b"");
+
+        let policy = RetryPolicy::new();
+        assert!(policy.is_retryable(&unavailable));
+        assert!(!policy.is_retryable(&not_found));
+        assert!(!policy.is_retryable(&QueryError::NotFoundError));
+
+        let policy = policy.retry_statuses(vec![StatusCode::NOT_FOUND]);
+        assert!(policy.is_retryable(&not_found));
+        assert!(!policy.is_retryable(&unavailable));
+
+        assert_eq!(RetryPolicy::none().max_attempts, 1);
+    }
+
+    fn client(server: &MockServer, max_attempts: u32) -> Q {
+        ClientBuilder::new()
+            .api_url(server.url())
+            .retry_policy(
+                RetryPolicy::new()
+                    .max_attempts(max_attempts)
+                    .initial_backoff(Duration::from_millis(1)),
+            )
+            .build()
+    }
+
+    #[tokio::test]
+    async fn retry() {
+        let server = MockServer::start();
+        server.inject(Fault::Status(503));
+        server.inject(Fault::Status(502));
+
+        let options = SearchOptions::new("1131", Language::Zh);
+        let courses = client(&server, 3).search(&options, true).await.unwrap();
+
+        assert!(!courses.is_empty());
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn retry_exhausted() {
+        let server = MockServer::start();
+        server.inject(Fault::Status(503));
+        server.inject(Fault::Status(503));
+
+        let result = client(&server, 2)
+            .query("1131", "CS1003302", Language::Zh)
+            .await;
+
+        assert_eq!(
+            result.unwrap_err().status(),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn no_retry_by_default() {
+        let server = MockServer::start();
+        server.inject(Fault::Status(503));
+
+        let client = ClientBuilder::new().api_url(server.url()).build();
+        let options = SearchOptions::new("1131", Language::Zh);
+        let result = client.search(&options, true).await;
+
+        assert!(matches!(result, Err(QueryError::StatusError { .. })));
+        assert_eq!(server.hits(), 1);
+    }
+}
@@ -0,0 +1,296 @@
+use std::time::Duration;
+
+use futures::{future::join_all, stream::BoxStream};
+
+use crate::{CourseDetails, CourseNo, Language, QueryError, Semester};
+
+use super::{
+    poll::{poll_stream, Poll, MIN_POLL_INTERVAL},
+    Q,
+};
+
+/// Default time between two polls of a `Watcher`.
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchEventKind {
+    /// The course was full and now has a free seat.
+    SeatOpened,
+    /// The course had free seats and is now full.
+    CourseFull,
+    /// `restrict1` or `restrict2` changed.
+    LimitChanged,
+    /// `choose_student` changed.
+    CountChanged,
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchEvent {
+    pub kind: WatchEventKind,
+    pub before: CourseDetails,
+    pub after: CourseDetails,
+}
+
+/// Polls a list of courses and reports enrollment changes, created by `Q::watch`.
+pub struct Watcher<'a> {
+    client: &'a Q,
+    semester: Semester,
+    course_nos: Vec<CourseNo>,
+    language: Language,
+    interval: Duration,
+}
+
+impl<'a> Watcher<'a> {
+    pub(crate) fn new(client: &'a Q, semester: Semester, course_nos: Vec<CourseNo>) -> Self {
+        Watcher {
+            client,
+            semester,
+            course_nos,
+            language: Language::Zh,
+            interval: DEFAULT_WATCH_INTERVAL,
+        }
+    }
+
+    /// Time between two polls, at least `MIN_POLL_INTERVAL`.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval.max(MIN_POLL_INTERVAL);
+        self
+    }
+
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Start polling.
+    ///
+    /// The first poll only records the initial state, later polls yield one event
+    /// per detected change. Query errors are yielded as they happen and polling continues.
+    pub fn stream(self) -> BoxStream<'a, Result<WatchEvent, QueryError>> {
+        let interval = self.interval;
+        let state = WatchState {
+            previous: vec![None; self.course_nos.len()],
+            watcher: self,
+        };
+
+        poll_stream(state, interval)
+    }
+}
+
+struct WatchState<'a> {
+    watcher: Watcher<'a>,
+    previous: Vec<Option<CourseDetails>>,
+}
+
+impl Poll for WatchState<'_> {
+    type Item = Result<WatchEvent, QueryError>;
+
+    async fn poll(&mut self) -> Vec<Self::Item> {
+        let mut events = Vec::new();
+        let watcher = &self.watcher;
+        let results = join_all(watcher.course_nos.iter().map(|course_no| {
+            watcher
+                .client
+                .query(watcher.semester, course_no, watcher.language)
+        }))
+        .await;
+
+        for (previous, result) in self.previous.iter_mut().zip(results) {
+            let after = match result {
+                Ok(after) => after,
+                Err(e) => {
+                    events.push(Err(e));
+                    continue;
+                }
+            };
+
+            if let Some(before) = previous.take() {
+                for kind in changes(&before, &after) {
+                    events.push(Ok(WatchEvent {
+                        kind,
+                        before: before.clone(),
+                        after: after.clone(),
+                    }));
+                }
+            }
+
+            *previous = Some(after);
+        }
+
+        events
+    }
+}
+
+/// Changes between two snapshots of the same course.
+fn changes(before: &CourseDetails, after: &CourseDetails) -> Vec<WatchEventKind> {
+    let mut kinds = Vec::new();
+
+    match (before.is_full(), after.is_full()) {
+        (true, false) => kinds.push(WatchEventKind::SeatOpened),
+        (false, true) => kinds.push(WatchEventKind::CourseFull),
+        _ => {}
+    }
+
+    if before.restrict1 != after.restrict1 || before.restrict2 != after.restrict2 {
+        kinds.push(WatchEventKind::LimitChanged);
+    }
+
+    if before.choose_student != after.choose_student {
+        kinds.push(WatchEventKind::CountChanged);
+    }
+
+    kinds
+}
+
+impl Q {
+    /// Watch the given courses for enrollment changes.
+    pub fn watch<S, I, C>(&self, semester: S, course_nos: I) -> Result<Watcher<'_>, QueryError>
+    where
+        S: TryInto<Semester>,
+        I: IntoIterator<Item = C>,
+        C: TryInto<CourseNo>,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
+        let semester = semester.try_into()?;
+        let course_nos = course_nos
+            .into_iter()
+            .map(|c| c.try_into().map_err(QueryError::from))
+            .collect::<Result<Vec<CourseNo>, QueryError>>()?;
+
+        Ok(Watcher::new(self, semester, course_nos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use serde_json::Value;
+
+    use crate::{
+        testing::{Fault, MockServer},
+        ClientBuilder,
+    };
+
+    fn details(choose_student: i32, restrict1: i32, restrict2: i32) -> CourseDetails {
+        CourseDetails {
+            choose_student,
+            restrict1,
+            restrict2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detect_changes() {
+        assert_eq!(
+            changes(&details(10, 9999, 50), &details(10, 9999, 50)),
+            vec![]
+        );
+        assert_eq!(
+            changes(&details(10, 9999, 50), &details(11, 9999, 50)),
+            vec![WatchEventKind::CountChanged]
+        );
+        assert_eq!(
+            changes(&details(50, 9999, 50), &details(49, 9999, 50)),
+            vec![WatchEventKind::SeatOpened, WatchEventKind::CountChanged]
+        );
+        assert_eq!(
+            changes(&details(49, 9999, 50), &details(50, 9999, 50)),
+            vec![WatchEventKind::CourseFull, WatchEventKind::CountChanged]
+        );
+        assert_eq!(
+            changes(&details(50, 9999, 50), &details(50, 9999, 60)),
+            vec![WatchEventKind::SeatOpened, WatchEventKind::LimitChanged]
+        );
+        assert_eq!(
+            changes(&details(50, 60, 9999), &details(50, 50, 9999)),
+            vec![WatchEventKind::CourseFull, WatchEventKind::LimitChanged]
+        );
+    }
+
+    /// The items of CS1003302 in a round of `watch`, up to the error of EE1003302.
+    async fn round(
+        events: &mut BoxStream<'_, Result<WatchEvent, QueryError>>,
+    ) -> Vec<Result<WatchEvent, QueryError>> {
+        let mut items = Vec::new();
+        loop {
+            match events.next().await.unwrap() {
+                Err(e) if e.is_not_found() => return items,
+                item => items.push(item),
+            }
+        }
+    }
+
+    fn kinds(items: Vec<Result<WatchEvent, QueryError>>) -> Vec<WatchEventKind> {
+        items.into_iter().map(|item| item.unwrap().kind).collect()
+    }
+
+    #[tokio::test]
+    async fn watch() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+        let set_choose_student = |n: i32| {
+            server.update("CS1003302", |record| {
+                record["ChooseStudent"] = Value::from(n.to_string());
+            })
+        };
+
+        // EE1003302 does not exist, its error ends every round
+        set_choose_student(60);
+        let mut events = client
+            .watch("1131", ["CS1003302", "EE1003302"])
+            .unwrap()
+            .interval(Duration::from_millis(1))
+            .stream();
+
+        // the first round only records CS1003302
+        assert!(round(&mut events).await.is_empty());
+
+        server.inject_for("CS1003302", Fault::Status(503));
+        let mut items = round(&mut events).await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items.remove(0).unwrap_err().status().unwrap(), 503);
+
+        set_choose_student(59);
+        let items = round(&mut events).await;
+        let event = items[0].as_ref().unwrap();
+        assert_eq!(event.before.choose_student, 60);
+        assert_eq!(event.after.choose_student, 59);
+        assert_eq!(
+            kinds(items),
+            vec![WatchEventKind::SeatOpened, WatchEventKind::CountChanged]
+        );
+
+        set_choose_student(58);
+        assert_eq!(
+            kinds(round(&mut events).await),
+            vec![WatchEventKind::CountChanged]
+        );
+    }
+
+    #[tokio::test]
+    async fn zero_interval() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        let events: Vec<_> = client
+            .watch("1131", ["EE1003302"])
+            .unwrap()
+            .interval(Duration::ZERO)
+            .stream()
+            .take(2)
+            .collect()
+            .await;
+        assert!(events
+            .iter()
+            .all(|e| e.as_ref().unwrap_err().is_not_found()));
+    }
+
+    #[test]
+    fn invalid_course_no() {
+        let client = Q::new();
+        assert!(client.watch("1131", ["CS1003302", "cs"]).is_err());
+        assert!(client.watch("1131", ["CS1003302"]).is_ok());
+    }
+}
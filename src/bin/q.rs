@@ -0,0 +1,373 @@
+use std::{io::Write, process::ExitCode};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use q::{CourseDetails, CourseFilter, CourseInfo, Language, SearchOptions, Q};
+
+/// Query the NTUST course database.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Table, global = true)]
+    format: Format,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Search courses
+    Search(SearchArgs),
+    /// Query the details of a single course
+    Query {
+        /// Semester, e.g. 1131
+        semester: String,
+        /// Course number, e.g. CS1003302
+        course_no: String,
+        /// Response language, zh or en
+        #[arg(short, long, value_parser = parse_language, default_value = "zh")]
+        lang: Language,
+    },
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// Semester, e.g. 1131
+    #[arg(short, long)]
+    semester: String,
+    /// Course number or prefix, e.g. CS
+    #[arg(short, long, default_value = "")]
+    course_no: String,
+    /// Course name
+    #[arg(short, long, default_value = "")]
+    name: String,
+    /// Teacher name
+    #[arg(short, long, default_value = "")]
+    teacher: String,
+    #[arg(long, default_value = "")]
+    dimension: String,
+    #[arg(long, default_value = "")]
+    notes: String,
+    /// Response language, zh or en
+    #[arg(short, long, value_parser = parse_language, default_value = "zh")]
+    lang: Language,
+    #[arg(long)]
+    foreign_language: bool,
+    #[arg(long)]
+    only_general: bool,
+    #[arg(long)]
+    only_ntust: bool,
+    #[arg(long)]
+    only_master: bool,
+    #[arg(long)]
+    only_undergraduate: bool,
+    #[arg(long)]
+    only_node: bool,
+    /// Keep one row per time slot group instead of merging them by course number
+    #[arg(long)]
+    no_merge: bool,
+    /// Filter the results locally, e.g. "credit>=2 day:Tue -period:1 has_seats"
+    #[arg(long, value_parser = parse_filter)]
+    filter: Option<CourseFilter>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+fn parse_language(input: &str) -> Result<Language, String> {
+    input
+        .parse()
+        .map_err(|_| format!("unknown language {:?}, expected zh or en", input))
+}
+
+fn parse_filter(input: &str) -> Result<CourseFilter, String> {
+    input.parse().map_err(|e: q::QueryError| e.to_string())
+}
+
+impl SearchArgs {
+    fn options(&self) -> SearchOptions {
+        let mut options = SearchOptions::new(&self.semester, self.lang);
+        options.course_no = self.course_no.clone();
+        options.course_name = self.name.clone();
+        options.course_teacher = self.teacher.clone();
+        options.dimension = self.dimension.clone();
+        options.course_notes = self.notes.clone();
+        options.foreign_language = self.foreign_language;
+        options.only_general = self.only_general;
+        options.only_ntust = self.only_ntust;
+        options.only_master = self.only_master;
+        options.only_under_graduate = self.only_undergraduate;
+        options.only_node = self.only_node;
+        options
+    }
+}
+
+const INFO_HEADERS: [&str; 9] = [
+    "course_no",
+    "course_name",
+    "teacher",
+    "credit",
+    "require",
+    "node",
+    "classroom",
+    "students",
+    "limit",
+];
+
+fn info_row(c: &CourseInfo) -> Vec<String> {
+    vec![
+        c.course_no.clone(),
+        c.course_name.clone(),
+        c.course_teacher.clone(),
+        c.credit_point.to_string(),
+        c.require_option.clone(),
+        c.node.clone().unwrap_or_default(),
+        c.class_room_no.clone().unwrap_or_default(),
+        c.choose_student.to_string(),
+        c.limit().to_string(),
+    ]
+}
+
+const DETAILS_HEADERS: [&str; 15] = [
+    "semester",
+    "course_no",
+    "course_name",
+    "teacher",
+    "credit",
+    "require",
+    "classroom",
+    "students",
+    "limit",
+    "url",
+    "object",
+    "content",
+    "textbook",
+    "grading",
+    "note",
+];
+
+fn details_row(d: &CourseDetails) -> Vec<String> {
+    vec![
+        d.semester.clone(),
+        d.course_no.clone(),
+        d.course_name.clone(),
+        d.course_teacher.clone(),
+        d.credit_point.to_string(),
+        d.require_option.clone(),
+        d.class_room_no.clone().unwrap_or_default(),
+        d.choose_student.to_string(),
+        d.limit().to_string(),
+        d.course_url.clone().unwrap_or_default(),
+        d.course_object.clone().unwrap_or_default(),
+        d.course_content.clone().unwrap_or_default(),
+        d.course_textbook.clone().unwrap_or_default(),
+        d.course_grading.clone().unwrap_or_default(),
+        d.course_note.clone().unwrap_or_default(),
+    ]
+}
+
+// full width characters take two columns in a terminal
+fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+fn write_table<W: Write>(
+    out: &mut W,
+    headers: &[&str],
+    rows: &[Vec<String>],
+) -> std::io::Result<()> {
+    let mut widths: Vec<usize> = headers.iter().map(|h| display_width(h)).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+
+    writeln!(out, "{}", table_row(headers.iter().copied(), &widths))?;
+    let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    writeln!(out, "{}", separator.join("-+-"))?;
+    for row in rows {
+        writeln!(
+            out,
+            "{}",
+            table_row(row.iter().map(String::as_str), &widths)
+        )?;
+    }
+
+    Ok(())
+}
+
+fn table_row<'a>(cells: impl Iterator<Item = &'a str>, widths: &[usize]) -> String {
+    let line: Vec<String> = cells
+        .zip(widths)
+        .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - display_width(cell))))
+        .collect();
+    line.join(" | ").trim_end().to_string()
+}
+
+/// Key-value listing, one field per line, for a single record.
+fn write_record<W: Write>(out: &mut W, headers: &[&str], row: &[String]) -> std::io::Result<()> {
+    let width = headers.iter().map(|h| h.len()).max().unwrap_or(0);
+    for (header, value) in headers.iter().zip(row) {
+        writeln!(
+            out,
+            "{:width$} : {}",
+            header,
+            value.replace('\n', " "),
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_csv<W: Write>(out: &mut W, headers: &[&str], rows: &[Vec<String>]) -> std::io::Result<()> {
+    let header: Vec<String> = headers.iter().map(|h| csv_field(h)).collect();
+    writeln!(out, "{}", header.join(","))?;
+    for row in rows {
+        let row: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let client = Q::new();
+    let mut out = std::io::stdout().lock();
+
+    match cli.command {
+        Command::Search(args) => {
+            let mut courses = client.search(&args.options(), !args.no_merge).await?;
+            if let Some(filter) = &args.filter {
+                courses = filter.apply(courses);
+            }
+            courses.sort_by(|a, b| a.course_no.cmp(&b.course_no));
+
+            match cli.format {
+                Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&courses)?)?,
+                Format::Csv => {
+                    let rows: Vec<_> = courses.iter().map(info_row).collect();
+                    write_csv(&mut out, &INFO_HEADERS, &rows)?
+                }
+                Format::Table => {
+                    let rows: Vec<_> = courses.iter().map(info_row).collect();
+                    write_table(&mut out, &INFO_HEADERS, &rows)?
+                }
+            }
+        }
+        Command::Query {
+            semester,
+            course_no,
+            lang,
+        } => {
+            let details = client.query(&semester, &course_no, lang).await?;
+
+            match cli.format {
+                Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&details)?)?,
+                Format::Csv => write_csv(&mut out, &DETAILS_HEADERS, &[details_row(&details)])?,
+                Format::Table => write_record(&mut out, &DETAILS_HEADERS, &details_row(&details))?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("q: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv() {
+        let mut out = Vec::new();
+        write_csv(
+            &mut out,
+            &["a", "b"],
+            &[vec!["1,2".to_string(), "say \"hi\"".to_string()]],
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "a,b\n\"1,2\",\"say \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn table() {
+        let mut out = Vec::new();
+        write_table(
+            &mut out,
+            &["no", "name"],
+            &[vec!["CS1003302".to_string(), "計算機".to_string()]],
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "no        | name\n----------+-------\nCS1003302 | 計算機\n"
+        );
+    }
+
+    #[test]
+    fn cli() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "q",
+            "query",
+            "1131",
+            "CS1003302",
+            "--lang",
+            "en",
+            "-f",
+            "json",
+        ])
+        .unwrap();
+        assert!(matches!(cli.format, Format::Json));
+        assert!(matches!(
+            cli.command,
+            Command::Query {
+                lang: Language::En,
+                ..
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["q", "query", "1131", "CS1003302", "--lang", "fr"]).is_err());
+    }
+}
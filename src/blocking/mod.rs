@@ -1,6 +1,11 @@
 pub use crate::CourseDetails;
 pub use crate::QueryError;
-use crate::{CourseInfo, Language, SearchOptions};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    cache::Cache, cassette::Cassette, BilingualCourse, CourseInfo, CourseNo, DetailedSearch,
+    Language, RateLimiter, RetryPolicy, SearchOptions, Semester,
+};
 
 use crate::async_impl;
 
@@ -25,6 +30,44 @@ impl ClientBuilder {
         self
     }
 
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.async_builder = self.async_builder.retry_policy(policy);
+        self
+    }
+
+    pub fn rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.async_builder = self.async_builder.rate_limiter(limiter);
+        self
+    }
+
+    /// # Panics
+    ///
+    /// If `requests_per_second` is not positive, see `RateLimiter::new`.
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.async_builder = self.async_builder.rate_limit(requests_per_second, burst);
+        self
+    }
+
+    pub fn cache(mut self, cache: Arc<dyn Cache>) -> Self {
+        self.async_builder = self.async_builder.cache(cache);
+        self
+    }
+
+    pub fn search_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.async_builder = self.async_builder.search_cache_ttl(ttl);
+        self
+    }
+
+    pub fn query_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.async_builder = self.async_builder.query_cache_ttl(ttl);
+        self
+    }
+
+    pub fn cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.async_builder = self.async_builder.cassette(cassette);
+        self
+    }
+
     pub fn build(self) -> Q {
         Q {
             async_q: self.async_builder.build(),
@@ -52,20 +95,70 @@ impl Q {
             .block_on(self.async_q.search(options, merge_courses))
     }
 
-    pub fn query(
+    pub fn query<S, C>(
         &self,
-        semester: &str,
-        course_no: &str,
+        semester: S,
+        course_no: C,
         language: Language,
-    ) -> Result<CourseDetails, QueryError> {
+    ) -> Result<CourseDetails, QueryError>
+    where
+        S: TryInto<Semester>,
+        C: TryInto<CourseNo>,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
         self.runtime
             .block_on(self.async_q.query(semester, course_no, language))
     }
+
+    /// Query many courses, at most `concurrency` at a time, results are in input order.
+    pub fn query_many<I, S, C>(
+        &self,
+        courses: I,
+        language: Language,
+        concurrency: usize,
+    ) -> Vec<Result<CourseDetails, QueryError>>
+    where
+        I: IntoIterator<Item = (S, C)>,
+        S: TryInto<Semester>,
+        C: TryInto<CourseNo>,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
+        self.runtime.block_on(
+            self.async_q
+                .query_many_ordered(courses, language, concurrency),
+        )
+    }
+
+    /// Fetch a course in both Chinese and English, see `async_impl::Q::query_bilingual`.
+    pub fn query_bilingual<S, C>(
+        &self,
+        semester: S,
+        course_no: C,
+    ) -> Result<BilingualCourse, QueryError>
+    where
+        S: TryInto<Semester>,
+        C: TryInto<CourseNo>,
+        QueryError: From<S::Error> + From<C::Error>,
+    {
+        self.runtime
+            .block_on(self.async_q.query_bilingual(semester, course_no))
+    }
+
+    /// Search, then query the details of every hit, see `async_impl::Q::search_detailed`.
+    pub fn search_detailed(
+        &self,
+        options: &SearchOptions,
+        concurrency: usize,
+    ) -> Result<DetailedSearch, QueryError> {
+        self.runtime
+            .block_on(self.async_q.search_detailed(options, concurrency))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::MockServer;
 
     #[test]
     fn new() {
@@ -74,33 +167,37 @@ mod tests {
 
     #[test]
     fn search() {
-        let client = Q::new();
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
 
         let mut options = SearchOptions::new("1131", Language::Zh);
 
         options.course_no = "cs".to_string();
 
-        let _details = client
+        let courses = client
             .search(&options, true)
             .expect("failed to search courses");
 
-        println!("{:#?}", _details);
+        assert!(!courses.is_empty());
+        assert!(courses.iter().all(|c| c.course_no.starts_with("CS")));
     }
 
     #[test]
     fn query() {
-        let client = Q::new();
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
 
-        let _details = client
-            .query("1122", "AT2005701", Language::Zh)
+        let details = client
+            .query("1131", "CS1003302", Language::Zh)
             .expect("Failed to query");
 
-        println!("{:#?}", _details)
+        assert_eq!(details.course_no, "CS1003302");
     }
 
     #[test]
     fn query_all_cs() {
-        let client = Q::new();
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
 
         let mut options = SearchOptions::new("1131", Language::Zh);
 
@@ -110,14 +207,14 @@ mod tests {
             .search(&options, true)
             .expect("failed to search courses");
 
-        let _ = search_results.iter().for_each(|c| {
-            let query_client = Q::new();
+        let courses = search_results
+            .iter()
+            .map(|c| (c.semester.as_str(), c.course_no.as_str()));
 
-            let details = query_client
-                .query(&c.semester, &c.course_no, Language::Zh)
-                .expect("Failed to query");
+        for details in client.query_many(courses, Language::Zh, crate::DEFAULT_CONCURRENCY) {
+            let details = details.expect("Failed to query");
 
-            println!("{:#?}", details)
-        });
+            assert!(details.course_no.starts_with("CS"));
+        }
     }
 }
@@ -0,0 +1,390 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+
+/// Default time to live of cached `search` and `query` responses.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Storage for raw response bodies.
+///
+/// Implementations must treat failures as misses, a broken cache never fails a request.
+pub trait Cache: Send + Sync + fmt::Debug {
+    /// Return the value if present and not expired.
+    fn get(&self, key: &str) -> Option<Bytes>;
+
+    fn put(&self, key: &str, value: Bytes, ttl: Duration);
+
+    fn remove(&self, key: &str);
+
+    fn clear(&self);
+}
+
+/// In-memory cache evicting the least recently used entry once `capacity` is reached.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    inner: Mutex<Lru>,
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<String, MemoryEntry>,
+    // last use tick -> key, oldest first
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    value: Bytes,
+    /// `None` if the TTL is too large to represent, the entry never expires.
+    expires_at: Option<Instant>,
+    tick: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.tick);
+            entry.tick = tick;
+            self.order.insert(tick, key.to_string());
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+        }
+    }
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        MemoryCache {
+            capacity: capacity.max(1),
+            inner: Mutex::new(Lru::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl Cache for MemoryCache {
+    fn get(&self, key: &str) -> Option<Bytes> {
+        let mut lru = self.inner.lock().unwrap();
+
+        match lru.entries.get(key) {
+            Some(entry) if entry.expires_at.is_none_or(|t| t > Instant::now()) => {
+                let value = entry.value.clone();
+                lru.touch(key);
+                Some(value)
+            }
+            Some(_) => {
+                lru.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn put(&self, key: &str, value: Bytes, ttl: Duration) {
+        let mut lru = self.inner.lock().unwrap();
+
+        lru.remove(key);
+
+        while lru.entries.len() >= self.capacity {
+            match lru.order.pop_first() {
+                Some((_, oldest)) => {
+                    lru.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+
+        lru.entries.insert(
+            key.to_string(),
+            MemoryEntry {
+                value,
+                expires_at: Instant::now().checked_add(ttl),
+                tick: 0,
+            },
+        );
+        lru.touch(key);
+    }
+
+    fn remove(&self, key: &str) {
+        self.inner.lock().unwrap().remove(key);
+    }
+
+    fn clear(&self) {
+        let mut lru = self.inner.lock().unwrap();
+        lru.entries.clear();
+        lru.order.clear();
+    }
+}
+
+/// Cache storing one file per entry in a directory.
+///
+/// Each file holds the expiry time as Unix milliseconds on the first line,
+/// the key on the second line and the raw value after that.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Create the directory if it does not exist yet.
+    pub fn new<P: AsRef<Path>>(dir: P) -> std::io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(DiskCache {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.cache", fnv1a(key.as_bytes())))
+    }
+}
+
+impl Cache for DiskCache {
+    fn get(&self, key: &str) -> Option<Bytes> {
+        let path = self.path(key);
+        let content = fs::read(&path).ok()?;
+
+        let mut parts = content.splitn(3, |b| *b == b'\n');
+        let expires_at = std::str::from_utf8(parts.next()?)
+            .ok()?
+            .parse::<u64>()
+            .ok()?;
+        let stored_key = parts.next()?;
+        let value = parts.next()?;
+
+        // a hash collision, treat as a miss
+        if stored_key != key.as_bytes() {
+            return None;
+        }
+
+        if expires_at <= unix_millis() {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        Some(Bytes::copy_from_slice(value))
+    }
+
+    fn put(&self, key: &str, value: Bytes, ttl: Duration) {
+        // keys are built by `Q` and never contain newlines
+        if key.contains('\n') {
+            return;
+        }
+
+        // a temporary file per write, so concurrent writers never share one
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        let path = self.path(key);
+        let tmp = path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        let write = || -> std::io::Result<()> {
+            let mut file = fs::File::create(&tmp)?;
+            writeln!(file, "{}", unix_millis().saturating_add(ttl))?;
+            writeln!(file, "{}", key)?;
+            file.write_all(&value)?;
+            fs::rename(&tmp, &path)
+        };
+
+        if write().is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+
+    fn clear(&self) {
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "cache") {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// stable across builds and platforms, unlike `DefaultHasher`
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::{testing::MockServer, ClientBuilder, Language, SearchOptions};
+
+    #[test]
+    fn memory_lru() {
+        let cache = MemoryCache::new(2);
+        let ttl = Duration::from_secs(60);
+
+        cache.put("a", Bytes::from_static(b"1"), ttl);
+        cache.put("b", Bytes::from_static(b"2"), ttl);
+        assert_eq!(cache.get("a").unwrap(), "1");
+
+        // "b" is now the least recently used
+        cache.put("c", Bytes::from_static(b"3"), ttl);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").unwrap(), "1");
+        assert_eq!(cache.get("c").unwrap(), "3");
+
+        cache.put("a", Bytes::from_static(b"4"), ttl);
+        assert_eq!(cache.get("a").unwrap(), "4");
+        assert_eq!(cache.len(), 2);
+
+        cache.remove("a");
+        assert!(cache.get("a").is_none());
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn memory_ttl() {
+        let cache = MemoryCache::new(8);
+
+        cache.put("a", Bytes::from_static(b"1"), Duration::ZERO);
+        assert!(cache.get("a").is_none());
+        assert!(cache.is_empty());
+
+        cache.put("forever", Bytes::from_static(b"1"), Duration::MAX);
+        assert_eq!(cache.get("forever").unwrap(), "1");
+    }
+
+    #[test]
+    fn disk() {
+        let dir = std::env::temp_dir().join(format!("q-disk-cache-{}", std::process::id()));
+        let cache = DiskCache::new(&dir).unwrap();
+        let ttl = Duration::from_secs(60);
+
+        cache.put("key", Bytes::from_static(b"[{\"a\":\n1}]"), ttl);
+        assert_eq!(cache.get("key").unwrap(), "[{\"a\":\n1}]");
+        assert!(cache.get("other").is_none());
+
+        cache.put("expired", Bytes::from_static(b"1"), Duration::ZERO);
+        assert!(cache.get("expired").is_none());
+
+        // sub-second TTLs are kept to the millisecond
+        cache.put(
+            "short",
+            Bytes::from_static(b"1"),
+            Duration::from_millis(500),
+        );
+        assert_eq!(cache.get("short").unwrap(), "1");
+        cache.put("forever", Bytes::from_static(b"1"), Duration::MAX);
+        assert_eq!(cache.get("forever").unwrap(), "1");
+
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        cache.put("shared", Bytes::from(format!("{}", i)), ttl);
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert!(cache.get("shared").is_some());
+        let leftovers = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        cache.clear();
+        assert!(cache.get("key").is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn client() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new()
+            .api_url(server.url())
+            .cache(Arc::new(MemoryCache::new(16)))
+            .search_cache_ttl(Duration::ZERO)
+            .build();
+
+        // not found is never cached
+        for _ in 0..2 {
+            assert!(client
+                .query("1131", "EE1003302", Language::Zh)
+                .await
+                .unwrap_err()
+                .is_not_found());
+        }
+        assert_eq!(server.hits(), 2);
+
+        let first = client
+            .query("1131", "CS1003302", Language::Zh)
+            .await
+            .unwrap();
+        let second = client
+            .query("1131", "cs1003302", Language::Zh)
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(server.hits(), 3);
+
+        // search caching is disabled by the zero TTL
+        let options = SearchOptions::new("1131", Language::Zh);
+        client.search(&options, true).await.unwrap();
+        client.search(&options, true).await.unwrap();
+        assert_eq!(server.hits(), 5);
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// Whether a cassette saves live traffic or serves it back.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Send requests to the server and save every request/response pair.
+    Record,
+    /// Answer requests from the saved pairs without touching the network.
+    Replay,
+}
+
+/// A recorded request/response pair.
+///
+/// `url` is relative to the client's API URL, e.g. `coursedetials?semester=1131&...`,
+/// so a cassette recorded against the live API can be replayed against any base URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub request_body: String,
+    pub status: u16,
+    /// The raw response body, kept as a string so that API quirks survive verbatim.
+    pub response_body: String,
+}
+
+impl Interaction {
+    fn matches(&self, method: &str, url: &str, body: &str) -> bool {
+        self.method == method && self.url == url && self.request_body == body
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Tape {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    tape: Tape,
+    // request key -> number of times it was replayed
+    played: HashMap<String, usize>,
+}
+
+/// A JSON file of HTTP interactions, used with `ClientBuilder::cassette`.
+///
+/// In replay mode identical requests are answered with their recordings in order,
+/// the last recording repeats once they run out.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    state: Mutex<State>,
+}
+
+impl Cassette {
+    /// Start a new recording, replacing the file if it exists.
+    ///
+    /// The file is rewritten after every interaction, so a recording survives a crash.
+    pub fn record<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let cassette = Cassette {
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Record,
+            state: Mutex::new(State::default()),
+        };
+        cassette.save(&cassette.state.lock().unwrap().tape)?;
+        Ok(cassette)
+    }
+
+    /// Load a recording to serve back.
+    pub fn replay<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let tape = serde_json::from_slice(&fs::read(path.as_ref())?)?;
+        Ok(Cassette {
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Replay,
+            state: Mutex::new(State {
+                tape,
+                played: HashMap::new(),
+            }),
+        })
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state.lock().unwrap().tape.interactions.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().tape.interactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Find the recorded status and body for a request.
+    pub(crate) fn play(&self, method: &str, url: &str, body: &str) -> Option<(u16, Bytes)> {
+        let mut state = self.state.lock().unwrap();
+
+        let key = format!("{} {} {}", method, url, body);
+        let played = state.played.get(&key).copied().unwrap_or(0);
+
+        let matching: Vec<&Interaction> = state
+            .tape
+            .interactions
+            .iter()
+            .filter(|i| i.matches(method, url, body))
+            .collect();
+        let found = matching.get(played).or(matching.last())?;
+        let response = (
+            found.status,
+            Bytes::copy_from_slice(found.response_body.as_bytes()),
+        );
+
+        state.played.insert(key, played + 1);
+        Some(response)
+    }
+
+    /// Append an interaction and rewrite the file, write failures are ignored.
+    pub(crate) fn append(&self, interaction: Interaction) {
+        let mut state = self.state.lock().unwrap();
+        state.tape.interactions.push(interaction);
+        let _ = self.save(&state.tape);
+    }
+
+    fn save(&self, tape: &Tape) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(tape)?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::MockServer, ClientBuilder, Language, QueryError, SearchOptions};
+    use reqwest::Url;
+    use std::sync::Arc;
+
+    fn interaction(url: &str, response_body: &str) -> Interaction {
+        Interaction {
+            method: String::from("GET"),
+            url: String::from(url),
+            request_body: String::new(),
+            status: 200,
+            response_body: String::from(response_body),
+        }
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let path = std::env::temp_dir().join(format!("q-cassette-{}.json", std::process::id()));
+
+        let recorder = Cassette::record(&path).unwrap();
+        assert!(recorder.is_empty());
+        recorder.append(interaction("a", "1"));
+        recorder.append(interaction("a", "2"));
+        recorder.append(interaction("b", "3"));
+
+        let player = Cassette::replay(&path).unwrap();
+        assert_eq!(player.mode(), Mode::Replay);
+        assert_eq!(player.interactions(), recorder.interactions());
+
+        // in order, then the last one repeats
+        assert_eq!(player.play("GET", "a", "").unwrap().1, "1");
+        assert_eq!(player.play("GET", "a", "").unwrap().1, "2");
+        assert_eq!(player.play("GET", "a", "").unwrap().1, "2");
+        assert_eq!(player.play("GET", "b", "").unwrap().1, "3");
+        assert!(player.play("POST", "b", "").is_none());
+        assert!(player.play("GET", "c", "").is_none());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn client() {
+        let path =
+            std::env::temp_dir().join(format!("q-cassette-client-{}.json", std::process::id()));
+        let options = SearchOptions::new("1131", Language::Zh);
+
+        let server = MockServer::start();
+        let recorder = Arc::new(Cassette::record(&path).unwrap());
+        let client = ClientBuilder::new()
+            .api_url(server.url())
+            .cassette(recorder.clone())
+            .build();
+
+        let courses = client.search(&options, false).await.unwrap();
+        let details = client
+            .query("1131", "CS1003302", Language::Zh)
+            .await
+            .unwrap();
+        assert_eq!(recorder.len(), 2);
+        assert_eq!(
+            recorder.interactions()[1].url,
+            "coursedetials?semester=1131&course_no=CS1003302&language=zh"
+        );
+        drop(server);
+
+        // no server behind this URL, everything comes from the cassette
+        let client = ClientBuilder::new()
+            .api_url(Url::parse("http://127.0.0.1:9/querycourse/api/").unwrap())
+            .cassette(Arc::new(Cassette::replay(&path).unwrap()))
+            .build();
+
+        assert_eq!(client.search(&options, false).await.unwrap(), courses);
+        assert_eq!(
+            client
+                .query("1131", "CS1003302", Language::Zh)
+                .await
+                .unwrap(),
+            details
+        );
+
+        let e = client
+            .query("1131", "CS2006301", Language::Zh)
+            .await
+            .unwrap_err();
+        assert!(matches!(e, QueryError::ReplayError(_)));
+
+        fs::remove_file(path).unwrap();
+    }
+}
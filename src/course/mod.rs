@@ -0,0 +1,397 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{CourseDetails, CourseInfo, CourseNo, Language, QueryError, Semester, TimeSlot};
+
+/// A course, normalized from either or both of `CourseInfo` and `CourseDetails`.
+///
+/// Fields only one of the wire structs carries are `Option`s, `None` meaning the
+/// source did not provide them rather than that the API left them empty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Course {
+    pub semester: Semester,
+    pub course_no: CourseNo,
+    pub course_name: String,
+    pub course_teacher: String,
+    pub credit_point: f32,
+    pub require_option: String,
+    pub all_year: String,
+    pub course_times: Option<i32>,
+    pub practical_times: Option<i32>,
+    pub choose_student: i32,
+    pub three_student: i32,
+    pub all_student: i32,
+    pub restrict1: i32,
+    pub restrict2: i32,
+    pub ntu_restrict: Option<i32>,
+    pub ntnu_restrict: Option<i32>,
+    pub class_room_no: Option<String>,
+    /// Only in search results.
+    pub dimension: Option<String>,
+    /// Only in search results.
+    pub node: Option<String>,
+    /// Only in search results.
+    pub three_node: Option<String>,
+    /// Only in search results.
+    pub contents: Option<String>,
+    /// Only in search results.
+    pub ntu_people: Option<i32>,
+    /// Only in search results.
+    pub ntnu_people: Option<i32>,
+    /// Only in search results.
+    pub abroad_people: Option<i32>,
+    /// Only in course details.
+    pub syllabus: Option<Syllabus>,
+}
+
+/// The syllabus part of `CourseDetails`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Syllabus {
+    pub core_ability: Option<String>,
+    pub course_url: Option<String>,
+    pub course_object: Option<String>,
+    pub course_content: Option<String>,
+    pub course_textbook: Option<String>,
+    pub course_refbook: Option<String>,
+    pub course_note: Option<String>,
+    pub course_grading: Option<String>,
+    pub course_remark: Option<String>,
+    pub instruction_1: Option<i32>,
+    pub instruction_2: Option<i32>,
+    pub instruction_3: Option<i32>,
+    pub instruction_4: Option<i32>,
+    pub instruction_other: Option<String>,
+}
+
+impl Course {
+    /// Join a search hit with its details, the details win where both have a value.
+    pub fn from_parts(info: CourseInfo, details: CourseDetails) -> Result<Self, QueryError> {
+        Ok(Course::try_from(info)?.merge(Course::try_from(details)?))
+    }
+
+    /// Combine two records of the same course, values present in `other` win.
+    pub fn merge(self, other: Course) -> Course {
+        Course {
+            semester: other.semester,
+            course_no: other.course_no,
+            course_name: other.course_name,
+            course_teacher: other.course_teacher,
+            credit_point: other.credit_point,
+            require_option: other.require_option,
+            all_year: other.all_year,
+            course_times: other.course_times.or(self.course_times),
+            practical_times: other.practical_times.or(self.practical_times),
+            choose_student: other.choose_student,
+            three_student: other.three_student,
+            all_student: other.all_student,
+            restrict1: other.restrict1,
+            restrict2: other.restrict2,
+            ntu_restrict: other.ntu_restrict.or(self.ntu_restrict),
+            ntnu_restrict: other.ntnu_restrict.or(self.ntnu_restrict),
+            class_room_no: other.class_room_no.or(self.class_room_no),
+            dimension: other.dimension.or(self.dimension),
+            node: other.node.or(self.node),
+            three_node: other.three_node.or(self.three_node),
+            contents: other.contents.or(self.contents),
+            ntu_people: other.ntu_people.or(self.ntu_people),
+            ntnu_people: other.ntnu_people.or(self.ntnu_people),
+            abroad_people: other.abroad_people.or(self.abroad_people),
+            syllabus: other.syllabus.or(self.syllabus),
+        }
+    }
+
+    pub fn limit(&self) -> i32 {
+        crate::seat_limit(self.restrict1, self.restrict2)
+    }
+
+    pub fn available_seats(&self) -> i32 {
+        crate::available_seats(self.choose_student, self.restrict1, self.restrict2)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.available_seats() == 0
+    }
+
+    /// Parse `node` into time slots, in chronological order.
+    pub fn time_slots(&self) -> Result<Vec<TimeSlot>, QueryError> {
+        crate::time_slots(self.node.as_deref())
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+impl TryFrom<CourseInfo> for Course {
+    type Error = QueryError;
+
+    fn try_from(info: CourseInfo) -> Result<Self, Self::Error> {
+        Ok(Course {
+            semester: info.semester.parse()?,
+            course_no: info.course_no.parse()?,
+            course_name: info.course_name,
+            course_teacher: info.course_teacher,
+            credit_point: info.credit_point,
+            require_option: info.require_option,
+            all_year: info.all_year,
+            course_times: info.course_times.trim().parse().ok(),
+            practical_times: info.practical_times.trim().parse().ok(),
+            choose_student: info.choose_student,
+            three_student: info.three_student,
+            all_student: info.all_student,
+            restrict1: info.restrict1,
+            restrict2: info.restrict2,
+            ntu_restrict: Some(info.nturestrict),
+            ntnu_restrict: Some(info.ntnurestrict),
+            class_room_no: info.class_room_no.and_then(non_empty),
+            dimension: non_empty(info.dimension),
+            node: info.node.and_then(non_empty),
+            three_node: info.three_node.and_then(non_empty),
+            contents: non_empty(info.contents),
+            ntu_people: Some(info.ntu_people),
+            ntnu_people: Some(info.ntnu_people),
+            abroad_people: Some(info.abroad_people),
+            syllabus: None,
+        })
+    }
+}
+
+impl TryFrom<CourseDetails> for Course {
+    type Error = QueryError;
+
+    fn try_from(details: CourseDetails) -> Result<Self, Self::Error> {
+        Ok(Course {
+            semester: details.semester.parse()?,
+            course_no: details.course_no.parse()?,
+            course_name: details.course_name,
+            course_teacher: details.course_teacher,
+            credit_point: details.credit_point,
+            require_option: details.require_option,
+            all_year: details.all_year,
+            course_times: Some(details.course_times),
+            practical_times: Some(details.practical_times),
+            choose_student: details.choose_student,
+            three_student: details.three_student,
+            all_student: details.all_student,
+            restrict1: details.restrict1,
+            restrict2: details.restrict2,
+            ntu_restrict: Some(details.nturestrict),
+            ntnu_restrict: details.ntnurestrict,
+            class_room_no: details.class_room_no,
+            dimension: None,
+            node: None,
+            three_node: None,
+            contents: None,
+            ntu_people: None,
+            ntnu_people: None,
+            abroad_people: None,
+            syllabus: Some(Syllabus {
+                core_ability: details.core_ability,
+                course_url: details.course_url,
+                course_object: details.course_object,
+                course_content: details.course_content,
+                course_textbook: details.course_textbook,
+                course_refbook: details.course_refbook,
+                course_note: details.course_note,
+                course_grading: details.course_grading,
+                course_remark: details.course_remark,
+                instruction_1: details.instruction_1,
+                instruction_2: details.instruction_2,
+                instruction_3: details.instruction_3,
+                instruction_4: details.instruction_4,
+                instruction_other: details.instruction_other,
+            }),
+        })
+    }
+}
+
+/// A value in both languages the API serves.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Localized<T> {
+    pub zh: T,
+    pub en: T,
+}
+
+impl<T> Localized<T> {
+    pub fn new(zh: T, en: T) -> Self {
+        Localized { zh, en }
+    }
+
+    pub fn get(&self, language: Language) -> &T {
+        match language {
+            Language::Zh => &self.zh,
+            Language::En => &self.en,
+        }
+    }
+
+    pub fn as_ref(&self) -> Localized<&T> {
+        Localized::new(&self.zh, &self.en)
+    }
+
+    pub fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> Localized<U> {
+        Localized::new(f(self.zh), f(self.en))
+    }
+}
+
+/// A course with its language dependent text in both Chinese and English,
+/// see `Q::query_bilingual`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BilingualCourse {
+    /// The Chinese record, for the fields that do not depend on the language.
+    pub course: Course,
+    pub course_name: Localized<String>,
+    pub course_teacher: Localized<String>,
+    pub contents: Localized<Option<String>>,
+    pub syllabus: Localized<Option<Syllabus>>,
+}
+
+impl BilingualCourse {
+    /// Fails if the records are not of the same course.
+    pub fn new(zh: Course, en: Course) -> Result<Self, QueryError> {
+        if zh.semester != en.semester || zh.course_no != en.course_no {
+            return Err(QueryError::InputError(format!(
+                "cannot combine {} {} with {} {}",
+                zh.semester, zh.course_no, en.semester, en.course_no
+            )));
+        }
+
+        Ok(BilingualCourse {
+            course_name: Localized::new(zh.course_name.clone(), en.course_name),
+            course_teacher: Localized::new(zh.course_teacher.clone(), en.course_teacher),
+            contents: Localized::new(zh.contents.clone(), en.contents),
+            syllabus: Localized::new(zh.syllabus.clone(), en.syllabus),
+            course: zh,
+        })
+    }
+
+    /// The course as it reads in `language`.
+    pub fn localize(&self, language: Language) -> Course {
+        Course {
+            course_name: self.course_name.get(language).clone(),
+            course_teacher: self.course_teacher.get(language).clone(),
+            contents: self.contents.get(language).clone(),
+            syllabus: self.syllabus.get(language).clone(),
+            ..self.course.clone()
+        }
+    }
+}
+
+/// Result of `Q::search_detailed`.
+///
+/// Courses whose details could not be fetched are reported in `failures`
+/// instead of failing the whole search.
+#[derive(Debug, Clone, Default)]
+pub struct DetailedSearch {
+    pub courses: Vec<Course>,
+    pub failures: Vec<(CourseInfo, QueryError)>,
+}
+
+impl DetailedSearch {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> CourseInfo {
+        CourseInfo {
+            semester: String::from("1131"),
+            course_no: String::from("CS1003302"),
+            course_name: String::from("計算機程式設計"),
+            course_times: String::from("3"),
+            practical_times: String::new(),
+            choose_student: 50,
+            restrict1: 9999,
+            restrict2: 60,
+            nturestrict: 5,
+            ntnurestrict: 5,
+            class_room_no: Some(String::from("TR-313")),
+            node: Some(String::from("R6,M3,M4")),
+            ..Default::default()
+        }
+    }
+
+    fn details() -> CourseDetails {
+        CourseDetails {
+            semester: String::from("1131"),
+            course_no: String::from("CS1003302"),
+            course_name: String::from("計算機程式設計"),
+            course_times: 3,
+            practical_times: 0,
+            choose_student: 57,
+            restrict1: 9999,
+            restrict2: 60,
+            ntnurestrict: None,
+            course_object: Some(String::from("programming")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalize() {
+        let from_info = Course::try_from(info()).unwrap();
+        assert_eq!(from_info.course_no.department(), "CS");
+        assert_eq!(from_info.course_times, Some(3));
+        assert_eq!(from_info.practical_times, None);
+        assert_eq!(from_info.dimension, None);
+        assert_eq!(from_info.syllabus, None);
+        assert_eq!(from_info.time_slots().unwrap().len(), 3);
+
+        let from_details = Course::try_from(details()).unwrap();
+        assert_eq!(from_details.practical_times, Some(0));
+        assert_eq!(from_details.node, None);
+        assert_eq!(from_details.ntnu_restrict, None);
+        assert!(from_details.syllabus.is_some());
+
+        let mut invalid = info();
+        invalid.semester = String::from("abc");
+        assert!(Course::try_from(invalid).unwrap_err().is_input());
+    }
+
+    #[test]
+    fn merge() {
+        let course = Course::from_parts(info(), details()).unwrap();
+
+        // fresher enrollment numbers from the details
+        assert_eq!(course.choose_student, 57);
+        assert_eq!(course.available_seats(), 3);
+        // kept from the search hit
+        assert_eq!(course.node.as_deref(), Some("R6,M3,M4"));
+        assert_eq!(course.class_room_no.as_deref(), Some("TR-313"));
+        assert_eq!(course.ntnu_restrict, Some(5));
+        assert_eq!(
+            course.syllabus.unwrap().course_object.as_deref(),
+            Some("programming")
+        );
+    }
+
+    #[test]
+    fn bilingual() {
+        let zh = Course::from_parts(info(), details()).unwrap();
+        let mut en = zh.clone();
+        en.course_name = String::from("Computer Programming");
+        en.syllabus = None;
+
+        let course = BilingualCourse::new(zh.clone(), en.clone()).unwrap();
+        assert_eq!(course.course_name.get(Language::Zh), "計算機程式設計");
+        assert_eq!(course.course_name.get(Language::En), "Computer Programming");
+        assert_eq!(course.localize(Language::Zh), zh);
+        assert_eq!(course.localize(Language::En), en);
+
+        en.course_no = "CS2006301".parse().unwrap();
+        assert!(BilingualCourse::new(zh, en).is_err());
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let course = Course::from_parts(info(), details()).unwrap();
+        let json = serde_json::to_string(&course).unwrap();
+        assert!(json.contains(r#""courseNo":"CS1003302""#));
+        assert_eq!(serde_json::from_str::<Course>(&json).unwrap(), course);
+    }
+}
@@ -0,0 +1,192 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+
+use crate::{CourseInfo, QueryError};
+
+const COURSE_NO_LEN: usize = 9;
+const SECTION_LEN: usize = 3;
+
+/// A full course number such as `"CS1003302"` or `"TCG046301"`.
+///
+/// The layout is a department prefix of letters, a year level digit,
+/// a serial and a trailing three character section:
+///
+/// | input       | department | level | serial | section |
+/// |-------------|------------|-------|--------|---------|
+/// | `CS1003302` | `CS`       | `1`   | `003`  | `302`   |
+/// | `TCG046301` | `TCG`      | `0`   | `46`   | `301`   |
+/// | `PE111B022` | `PE`       | `1`   | `11B`  | `022`   |
+///
+/// Parsing is case-insensitive, the stored form is uppercase.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct CourseNo {
+    raw: String,
+    department_len: usize,
+}
+
+impl CourseNo {
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn department(&self) -> &str {
+        &self.raw[..self.department_len]
+    }
+
+    pub fn level(&self) -> u8 {
+        self.raw.as_bytes()[self.department_len] - b'0'
+    }
+
+    pub fn serial(&self) -> &str {
+        &self.raw[self.department_len + 1..COURSE_NO_LEN - SECTION_LEN]
+    }
+
+    pub fn section(&self) -> &str {
+        &self.raw[COURSE_NO_LEN - SECTION_LEN..]
+    }
+}
+
+impl FromStr for CourseNo {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<CourseNo, Self::Err> {
+        let invalid = || QueryError::InputError(format!("invalid course number: {:?}", input));
+
+        let raw = input.to_ascii_uppercase();
+
+        if raw.len() != COURSE_NO_LEN || !raw.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+
+        let department_len = raw.bytes().take_while(u8::is_ascii_alphabetic).count();
+
+        if !(2..=4).contains(&department_len) || !raw.as_bytes()[department_len].is_ascii_digit() {
+            return Err(invalid());
+        }
+
+        Ok(CourseNo {
+            raw,
+            department_len,
+        })
+    }
+}
+
+impl fmt::Display for CourseNo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl AsRef<str> for CourseNo {
+    fn as_ref(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl TryFrom<&str> for CourseNo {
+    type Error = QueryError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<&String> for CourseNo {
+    type Error = QueryError;
+
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for CourseNo {
+    type Error = QueryError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<&CourseNo> for CourseNo {
+    fn from(value: &CourseNo) -> Self {
+        value.clone()
+    }
+}
+
+/// Group courses by the department prefix of their course number.
+///
+/// Courses whose `course_no` cannot be parsed are left out.
+pub fn group_by_department<'a, I>(courses: I) -> BTreeMap<String, Vec<&'a CourseInfo>>
+where
+    I: IntoIterator<Item = &'a CourseInfo>,
+{
+    let mut groups: BTreeMap<String, Vec<&'a CourseInfo>> = BTreeMap::new();
+
+    for course in courses {
+        if let Ok(course_no) = course.course_no.parse::<CourseNo>() {
+            groups
+                .entry(course_no.department().to_string())
+                .or_default()
+                .push(course);
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let no: CourseNo = "CS1003302".parse().unwrap();
+        assert_eq!(no.department(), "CS");
+        assert_eq!(no.level(), 1);
+        assert_eq!(no.serial(), "003");
+        assert_eq!(no.section(), "302");
+
+        let no: CourseNo = "tcg046301".parse().unwrap();
+        assert_eq!(no.as_str(), "TCG046301");
+        assert_eq!(no.department(), "TCG");
+        assert_eq!(no.level(), 0);
+        assert_eq!(no.serial(), "46");
+        assert_eq!(no.section(), "301");
+
+        let no: CourseNo = "PE111B022".parse().unwrap();
+        assert_eq!(no.department(), "PE");
+        assert_eq!(no.serial(), "11B");
+
+        for input in [
+            "",
+            "cs",
+            "CS100330",
+            "CS10033022",
+            "C11003302",
+            "CSABC3302",
+            "CS-003302",
+        ] {
+            assert!(input.parse::<CourseNo>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn group() {
+        let courses: Vec<CourseInfo> = ["CS1003302", "CS2006302", "GE3729302", "invalid"]
+            .into_iter()
+            .map(|no| CourseInfo {
+                course_no: no.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        let groups = group_by_department(&courses);
+
+        assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["CS", "GE"]);
+        assert_eq!(groups["CS"].len(), 2);
+        assert_eq!(groups["GE"][0].course_no, "GE3729302");
+    }
+}
@@ -0,0 +1,281 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{merge_courses, CourseInfo, TimeSlot};
+
+/// A field of `CourseInfo` compared by [`diff`].
+///
+/// Enrollment counts change all the time and are left out, see `Q::watch` for those.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Field {
+    CourseName,
+    CourseTeacher,
+    Dimension,
+    CreditPoint,
+    RequireOption,
+    AllYear,
+    CourseTimes,
+    PracticalTimes,
+    /// The lower of `restrict1` and `restrict2`.
+    Limit,
+    ClassRoomNo,
+    /// Compared as time slots, so reordering the node list is not a change.
+    Node,
+    Contents,
+}
+
+impl Field {
+    pub const ALL: [Field; 12] = [
+        Field::CourseName,
+        Field::CourseTeacher,
+        Field::Dimension,
+        Field::CreditPoint,
+        Field::RequireOption,
+        Field::AllYear,
+        Field::CourseTimes,
+        Field::PracticalTimes,
+        Field::Limit,
+        Field::ClassRoomNo,
+        Field::Node,
+        Field::Contents,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Field::CourseName => "course_name",
+            Field::CourseTeacher => "course_teacher",
+            Field::Dimension => "dimension",
+            Field::CreditPoint => "credit_point",
+            Field::RequireOption => "require_option",
+            Field::AllYear => "all_year",
+            Field::CourseTimes => "course_times",
+            Field::PracticalTimes => "practical_times",
+            Field::Limit => "limit",
+            Field::ClassRoomNo => "class_room_no",
+            Field::Node => "node",
+            Field::Contents => "contents",
+        }
+    }
+
+    /// The value of this field as displayed in a change.
+    fn value(&self, course: &CourseInfo) -> String {
+        match self {
+            Field::CourseName => course.course_name.clone(),
+            Field::CourseTeacher => course.course_teacher.clone(),
+            Field::Dimension => course.dimension.clone(),
+            Field::CreditPoint => course.credit_point.to_string(),
+            Field::RequireOption => course.require_option.clone(),
+            Field::AllYear => course.all_year.clone(),
+            Field::CourseTimes => course.course_times.clone(),
+            Field::PracticalTimes => course.practical_times.clone(),
+            Field::Limit => course.limit().to_string(),
+            Field::ClassRoomNo => course.class_room_no.clone().unwrap_or_default(),
+            Field::Node => match course.time_slots() {
+                Ok(slots) => slots
+                    .iter()
+                    .map(TimeSlot::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                Err(_) => course.node.clone().unwrap_or_default(),
+            },
+            Field::Contents => course.contents.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: Field,
+    pub before: String,
+    pub after: String,
+}
+
+/// A course present in both snapshots with at least one changed field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CourseChange {
+    pub before: CourseInfo,
+    pub after: CourseInfo,
+    pub changes: Vec<FieldChange>,
+}
+
+impl CourseChange {
+    pub fn get(&self, field: Field) -> Option<&FieldChange> {
+        self.changes.iter().find(|c| c.field == field)
+    }
+}
+
+/// Differences between two search results, each list ordered by semester and course number.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatalogDiff {
+    pub added: Vec<CourseInfo>,
+    pub removed: Vec<CourseInfo>,
+    pub changed: Vec<CourseChange>,
+}
+
+impl CatalogDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// One line per course, `+` for added, `-` for removed and `~` for changed courses.
+impl fmt::Display for CatalogDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for course in &self.added {
+            writeln!(f, "+ {} {}", course.course_no, course.course_name)?;
+        }
+        for course in &self.removed {
+            writeln!(f, "- {} {}", course.course_no, course.course_name)?;
+        }
+        for change in &self.changed {
+            let fields: Vec<String> = change
+                .changes
+                .iter()
+                .map(|c| format!("{}: {:?} -> {:?}", c.field, c.before, c.after))
+                .collect();
+            writeln!(
+                f,
+                "~ {} {} ({})",
+                change.after.course_no,
+                change.after.course_name,
+                fields.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare two search results of the same catalog taken at different times.
+///
+/// Courses are matched by semester and course number, split time slot groups
+/// are merged first as `merge_courses` does.
+pub fn diff(before: &[CourseInfo], after: &[CourseInfo]) -> CatalogDiff {
+    let index = |courses: &[CourseInfo]| -> BTreeMap<(String, String), CourseInfo> {
+        merge_courses(courses.to_vec())
+            .into_iter()
+            .map(|c| ((c.semester.clone(), c.course_no.clone()), c))
+            .collect()
+    };
+
+    let before = index(before);
+    let mut after = index(after);
+    let mut result = CatalogDiff::default();
+
+    for (key, old) in before {
+        let Some(new) = after.remove(&key) else {
+            result.removed.push(old);
+            continue;
+        };
+
+        let changes: Vec<FieldChange> = Field::ALL
+            .iter()
+            .filter_map(|field| {
+                let (before, after) = (field.value(&old), field.value(&new));
+                (before != after).then_some(FieldChange {
+                    field: *field,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+
+        if !changes.is_empty() {
+            result.changed.push(CourseChange {
+                before: old,
+                after: new,
+                changes,
+            });
+        }
+    }
+
+    result.added = after.into_values().collect();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course(course_no: &str, teacher: &str, node: &str) -> CourseInfo {
+        CourseInfo {
+            semester: String::from("1131"),
+            course_no: String::from(course_no),
+            course_name: format!("course {}", course_no),
+            course_teacher: String::from(teacher),
+            restrict1: 9999,
+            restrict2: 50,
+            node: Some(String::from(node)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn changes() {
+        let before = vec![
+            course("CS1003302", "A", "M3,M4"),
+            course("CS2006301", "B", "T2"),
+            course("CS3001301", "C", "W6,W5"),
+        ];
+
+        let mut moved = course("CS1003302", "A", "M4,R6");
+        moved.choose_student = 40;
+        let mut swapped = course("CS2006301", "D", "T2");
+        swapped.restrict2 = 60;
+        let after = vec![
+            moved,
+            swapped,
+            course("CS4001301", "E", "F1"),
+            // the same node list in another order
+            course("CS3001301", "C", "W5,W6"),
+        ];
+
+        let result = diff(&before, &after);
+
+        assert_eq!(result.added.len(), 1);
+        assert_eq!(result.added[0].course_no, "CS4001301");
+        assert!(result.removed.is_empty());
+        assert_eq!(result.changed.len(), 2);
+        assert!(result
+            .changed
+            .iter()
+            .all(|c| c.after.course_no != "CS3001301"));
+
+        let node = result.changed[0].get(Field::Node).unwrap();
+        assert_eq!(
+            (node.before.as_str(), node.after.as_str()),
+            ("M3,M4", "M4,R6")
+        );
+        // enrollment counts are not compared
+        assert_eq!(result.changed[0].changes.len(), 1);
+
+        let fields: Vec<Field> = result.changed[1].changes.iter().map(|c| c.field).collect();
+        assert_eq!(fields, vec![Field::CourseTeacher, Field::Limit]);
+
+        let text = result.to_string();
+        assert!(text.contains("+ CS4001301"));
+        assert!(text.contains(
+            r#"~ CS2006301 course CS2006301 (course_teacher: "B" -> "D", limit: "50" -> "60")"#
+        ));
+
+        let reverse = diff(&after, &before);
+        assert_eq!(reverse.removed.len(), 1);
+        assert!(diff(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn split_groups() {
+        // the API returns one entry per time slot group
+        let before = vec![
+            course("CS1003302", "A", "M3"),
+            course("CS1003302", "A", "R6"),
+        ];
+        let after = vec![course("CS1003302", "A", "R6,M3")];
+
+        assert!(diff(&before, &after).is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+//! Enrollment time series, sampled by `Q::record`.
+//!
+//! Samples are appended to a [`SampleStore`], in memory, as JSON lines in a file,
+//! or with the `store` feature in a `store::Store`. [`stats`] turns them into
+//! fill rate, time-to-full and peak-demand hour per course.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, TimestampMilliSeconds};
+
+use crate::{from_json, CourseDetails, CourseInfo, QueryError, UTC_OFFSET_HOURS};
+
+/// Enrollment counts of a course at one point in time.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+    pub semester: String,
+    pub course_no: String,
+    /// Serialized as Unix milliseconds.
+    #[serde_as(as = "TimestampMilliSeconds<i64>")]
+    pub sampled_at: SystemTime,
+    pub choose_student: i32,
+    pub all_student: i32,
+    pub three_student: i32,
+    /// `None` if the course had no search hit to take it from.
+    pub ntu_people: Option<i32>,
+    pub ntnu_people: Option<i32>,
+    pub abroad_people: Option<i32>,
+    pub restrict1: i32,
+    pub restrict2: i32,
+}
+
+impl Sample {
+    /// Counts from the details of a course, plus the NTU, NTNU and abroad counts
+    /// that only come with its search hit.
+    pub fn new(details: &CourseDetails, info: Option<&CourseInfo>, sampled_at: SystemTime) -> Self {
+        Sample {
+            semester: details.semester.clone(),
+            course_no: details.course_no.clone(),
+            sampled_at,
+            choose_student: details.choose_student,
+            all_student: details.all_student,
+            three_student: details.three_student,
+            ntu_people: info.map(|c| c.ntu_people),
+            ntnu_people: info.map(|c| c.ntnu_people),
+            abroad_people: info.map(|c| c.abroad_people),
+            restrict1: details.restrict1,
+            restrict2: details.restrict2,
+        }
+    }
+
+    pub fn limit(&self) -> i32 {
+        crate::seat_limit(self.restrict1, self.restrict2)
+    }
+
+    pub fn is_full(&self) -> bool {
+        crate::available_seats(self.choose_student, self.restrict1, self.restrict2) == 0
+    }
+
+    /// `choose_student` over the limit, a course without seats counts as full.
+    pub fn fill_rate(&self) -> f64 {
+        match self.limit() {
+            limit if limit > 0 => self.choose_student as f64 / limit as f64,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Where `Q::record` appends its samples.
+pub trait SampleStore: Send + Sync {
+    fn append(&self, samples: &[Sample]) -> Result<(), QueryError>;
+
+    /// Every sample stored so far, in the order they were appended.
+    fn samples(&self) -> Result<Vec<Sample>, QueryError>;
+}
+
+impl<T: SampleStore + ?Sized> SampleStore for Arc<T> {
+    fn append(&self, samples: &[Sample]) -> Result<(), QueryError> {
+        (**self).append(samples)
+    }
+
+    fn samples(&self) -> Result<Vec<Sample>, QueryError> {
+        (**self).samples()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemorySamples {
+    samples: Mutex<Vec<Sample>>,
+}
+
+impl MemorySamples {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SampleStore for MemorySamples {
+    fn append(&self, samples: &[Sample]) -> Result<(), QueryError> {
+        self.samples.lock().unwrap().extend_from_slice(samples);
+        Ok(())
+    }
+
+    fn samples(&self) -> Result<Vec<Sample>, QueryError> {
+        Ok(self.samples.lock().unwrap().clone())
+    }
+}
+
+/// Samples as JSON lines appended to a file, one sample per line.
+#[derive(Debug)]
+pub struct JsonlSamples {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl JsonlSamples {
+    /// Use the file at `path`, it is created on the first append.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        JsonlSamples {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl SampleStore for JsonlSamples {
+    fn append(&self, samples: &[Sample]) -> Result<(), QueryError> {
+        let mut lines = Vec::new();
+        for sample in samples {
+            serde_json::to_writer(&mut lines, sample).expect("samples always serialize");
+            lines.push(b'\n');
+        }
+
+        let _lock = self.lock.lock().unwrap();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(&lines)?;
+        Ok(())
+    }
+
+    fn samples(&self) -> Result<Vec<Sample>, QueryError> {
+        let _lock = self.lock.lock().unwrap();
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        data.split(|b| *b == b'\n')
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(from_json)
+            .collect()
+    }
+}
+
+/// Enrollment statistics of one course over its samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CourseStats {
+    pub semester: String,
+    pub course_no: String,
+    pub samples: usize,
+    pub first_sampled_at: SystemTime,
+    pub last_sampled_at: SystemTime,
+    /// Fill rate of the latest sample.
+    pub fill_rate: f64,
+    /// Time from the first sample to the first full one, `None` if the course never filled up.
+    ///
+    /// A course already full when recording started reports zero.
+    pub time_to_full: Option<Duration>,
+    /// Hour of day in Taiwan time (0-23) in which `choose_student` grew the most,
+    /// `None` if it never grew.
+    pub peak_hour: Option<u8>,
+}
+
+/// Statistics per course, ordered by semester and course number.
+pub fn stats(samples: &[Sample]) -> Vec<CourseStats> {
+    let mut courses: BTreeMap<(&str, &str), Vec<&Sample>> = BTreeMap::new();
+    for sample in samples {
+        courses
+            .entry((&sample.semester, &sample.course_no))
+            .or_default()
+            .push(sample);
+    }
+
+    courses
+        .into_values()
+        .map(|mut samples| {
+            samples.sort_by_key(|s| s.sampled_at);
+            course_stats(&samples)
+        })
+        .collect()
+}
+
+fn course_stats(samples: &[&Sample]) -> CourseStats {
+    let (first, last) = (samples[0], samples[samples.len() - 1]);
+
+    let time_to_full = samples.iter().find(|s| s.is_full()).map(|s| {
+        s.sampled_at
+            .duration_since(first.sampled_at)
+            .unwrap_or_default()
+    });
+
+    let mut growth = [0i64; 24];
+    for pair in samples.windows(2) {
+        let added = pair[1].choose_student - pair[0].choose_student;
+        if added > 0 {
+            growth[taipei_hour(pair[1].sampled_at) as usize] += added as i64;
+        }
+    }
+    let peak_hour = (0..24u8)
+        .filter(|h| growth[*h as usize] > 0)
+        .max_by_key(|h| (growth[*h as usize], std::cmp::Reverse(*h)));
+
+    CourseStats {
+        semester: first.semester.clone(),
+        course_no: first.course_no.clone(),
+        samples: samples.len(),
+        first_sampled_at: first.sampled_at,
+        last_sampled_at: last.sampled_at,
+        fill_rate: last.fill_rate(),
+        time_to_full,
+        peak_hour,
+    }
+}
+
+/// Hour of day in Asia/Taipei, which has no daylight saving time.
+fn taipei_hour(time: SystemTime) -> u8 {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        + UTC_OFFSET_HOURS as u64 * 3600;
+    ((secs / 3600) % 24) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sample of CS1003302 at `hour:minute` Taiwan time on 2024-09-02.
+    fn sample(hour: u64, minute: u64, choose_student: i32) -> Sample {
+        // 2024-09-02T00:00:00+08:00
+        let midnight = UNIX_EPOCH + Duration::from_secs(1_725_206_400);
+        Sample {
+            semester: String::from("1131"),
+            course_no: String::from("CS1003302"),
+            sampled_at: midnight + Duration::from_secs(hour * 3600 + minute * 60),
+            choose_student,
+            all_student: choose_student,
+            three_student: 0,
+            ntu_people: None,
+            ntnu_people: None,
+            abroad_people: None,
+            restrict1: 9999,
+            restrict2: 50,
+        }
+    }
+
+    #[test]
+    fn course_stats() {
+        let mut other = sample(9, 0, 10);
+        other.course_no = String::from("CS2006301");
+
+        let samples = vec![
+            sample(9, 0, 0),
+            other,
+            sample(9, 5, 30),
+            sample(10, 0, 45),
+            // someone dropped the course
+            sample(10, 30, 44),
+            sample(11, 0, 50),
+        ];
+
+        let stats = stats(&samples);
+        assert_eq!(stats.len(), 2);
+
+        let cs = &stats[0];
+        assert_eq!(cs.course_no, "CS1003302");
+        assert_eq!(cs.samples, 5);
+        assert_eq!(cs.fill_rate, 1.0);
+        assert_eq!(cs.time_to_full, Some(Duration::from_secs(2 * 3600)));
+        assert_eq!(cs.peak_hour, Some(9));
+
+        let other = &stats[1];
+        assert_eq!(other.fill_rate, 0.2);
+        assert_eq!(other.time_to_full, None);
+        assert_eq!(other.peak_hour, None);
+    }
+
+    #[test]
+    fn jsonl() {
+        let path = std::env::temp_dir().join(format!("q-samples-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let store = JsonlSamples::new(&path);
+        assert!(store.samples().unwrap().is_empty());
+
+        store.append(&[sample(9, 0, 1), sample(9, 1, 2)]).unwrap();
+        store.append(&[sample(9, 2, 3)]).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 3);
+        assert!(text.starts_with(
+            r#"{"semester":"1131","courseNo":"CS1003302","sampledAt":1725238800000,"#
+        ));
+
+        let samples = store.samples().unwrap();
+        assert_eq!(
+            samples,
+            vec![sample(9, 0, 1), sample(9, 1, 2), sample(9, 2, 3)]
+        );
+
+        fs::write(&path, "{}\n").unwrap();
+        assert!(store.samples().unwrap_err().is_parse());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,485 @@
+//! Client-side filtering of search results.
+//!
+//! A [`CourseFilter`] keeps the courses matching all of its conditions. It is built
+//! either with its builder methods or parsed from a query such as
+//! `credit>=2 day:Tue -period:1 has_seats`:
+//!
+//! | Term | Matches courses |
+//! |------|-----------------|
+//! | `credit>=2` | by credit, with `=`, `!=`, `<`, `<=`, `>` or `>=` |
+//! | `day:Tue`, `day:T` | with a class on that weekday |
+//! | `period:1`, `period:A` | with a class in that period on any day |
+//! | `slot:M3` | with a class in that time slot |
+//! | `required`, `elective` | required (`R`) or elective (`E`) |
+//! | `require:R` | by the exact `require_option` |
+//! | `has_seats` | not full yet |
+//! | `no:CS10` | whose course number starts with the prefix |
+//! | `name:程式`, `teacher:金` | whose name or teacher contains the text |
+//! | `dimension:A` | whose dimension contains the text |
+//!
+//! A leading `-` negates a term, values with spaces can be double quoted, e.g. `name:"Computer Programming"`.
+//! Inside quotes, `\"` and `\\` stand for a literal `"` and `\`.
+
+use std::{fmt, str::FromStr};
+
+use crate::{CourseInfo, Period, QueryError, TimeSlot, Weekday};
+
+/// Comparison of a `credit` term.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+
+    pub fn compare<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single condition on a course.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Credit(Comparison, f32),
+    Day(Weekday),
+    Period(Period),
+    Slot(TimeSlot),
+    RequireOption(String),
+    HasSeats,
+    CourseNo(String),
+    Name(String),
+    Teacher(String),
+    Dimension(String),
+    Not(Box<Condition>),
+}
+
+// `require_option` codes of the API
+const REQUIRED: &str = "R";
+const ELECTIVE: &str = "E";
+
+impl Condition {
+    /// Courses whose node cannot be parsed have no time slots.
+    pub fn matches(&self, course: &CourseInfo) -> bool {
+        let slots = || course.time_slots().unwrap_or_default();
+
+        match self {
+            Condition::Credit(comparison, credit) => {
+                comparison.compare(course.credit_point, *credit)
+            }
+            Condition::Day(weekday) => slots().iter().any(|s| s.weekday == *weekday),
+            Condition::Period(period) => slots().iter().any(|s| s.period == *period),
+            Condition::Slot(slot) => slots().contains(slot),
+            Condition::RequireOption(option) => course.require_option == *option,
+            Condition::HasSeats => !course.is_full(),
+            Condition::CourseNo(prefix) => course
+                .course_no
+                .to_ascii_uppercase()
+                .starts_with(&prefix.to_ascii_uppercase()),
+            Condition::Name(text) => contains(&course.course_name, text),
+            Condition::Teacher(text) => contains(&course.course_teacher, text),
+            Condition::Dimension(text) => contains(&course.dimension, text),
+            Condition::Not(condition) => !condition.matches(course),
+        }
+    }
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Formats back to the query syntax.
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Credit(comparison, credit) => write!(f, "credit{}{}", comparison, credit),
+            Condition::Day(weekday) => write!(f, "day:{:?}", weekday),
+            Condition::Period(period) => write!(f, "period:{}", period),
+            Condition::Slot(slot) => write!(f, "slot:{}", slot),
+            Condition::RequireOption(option) if option == REQUIRED => f.write_str("required"),
+            Condition::RequireOption(option) if option == ELECTIVE => f.write_str("elective"),
+            Condition::RequireOption(option) => write!(f, "require:{}", quote(option)),
+            Condition::HasSeats => f.write_str("has_seats"),
+            Condition::CourseNo(prefix) => write!(f, "no:{}", quote(prefix)),
+            Condition::Name(text) => write!(f, "name:{}", quote(text)),
+            Condition::Teacher(text) => write!(f, "teacher:{}", quote(text)),
+            Condition::Dimension(text) => write!(f, "dimension:{}", quote(text)),
+            Condition::Not(condition) => write!(f, "-{}", condition),
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Keeps the courses matching every condition, an empty filter keeps everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CourseFilter {
+    conditions: Vec<Condition>,
+}
+
+impl CourseFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn and(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn not(self, condition: Condition) -> Self {
+        self.and(Condition::Not(Box::new(condition)))
+    }
+
+    pub fn credit(self, comparison: Comparison, credit: f32) -> Self {
+        self.and(Condition::Credit(comparison, credit))
+    }
+
+    pub fn day(self, weekday: Weekday) -> Self {
+        self.and(Condition::Day(weekday))
+    }
+
+    pub fn period(self, period: Period) -> Self {
+        self.and(Condition::Period(period))
+    }
+
+    pub fn slot(self, slot: TimeSlot) -> Self {
+        self.and(Condition::Slot(slot))
+    }
+
+    pub fn required(self) -> Self {
+        self.and(Condition::RequireOption(String::from(REQUIRED)))
+    }
+
+    pub fn elective(self) -> Self {
+        self.and(Condition::RequireOption(String::from(ELECTIVE)))
+    }
+
+    pub fn has_seats(self) -> Self {
+        self.and(Condition::HasSeats)
+    }
+
+    pub fn course_no<T: Into<String>>(self, prefix: T) -> Self {
+        self.and(Condition::CourseNo(prefix.into()))
+    }
+
+    pub fn name<T: Into<String>>(self, text: T) -> Self {
+        self.and(Condition::Name(text.into()))
+    }
+
+    pub fn teacher<T: Into<String>>(self, text: T) -> Self {
+        self.and(Condition::Teacher(text.into()))
+    }
+
+    pub fn dimension<T: Into<String>>(self, text: T) -> Self {
+        self.and(Condition::Dimension(text.into()))
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    pub fn matches(&self, course: &CourseInfo) -> bool {
+        self.conditions.iter().all(|c| c.matches(course))
+    }
+
+    /// Keep the matching courses, in their original order.
+    pub fn apply(&self, mut courses: Vec<CourseInfo>) -> Vec<CourseInfo> {
+        courses.retain(|c| self.matches(c));
+        courses
+    }
+
+    pub fn filter<'a, I>(&'a self, courses: I) -> impl Iterator<Item = &'a CourseInfo> + 'a
+    where
+        I: IntoIterator<Item = &'a CourseInfo>,
+        I::IntoIter: 'a,
+    {
+        courses.into_iter().filter(move |c| self.matches(c))
+    }
+}
+
+impl FromStr for CourseFilter {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        tokenize(input)?
+            .into_iter()
+            .map(|term| parse_term(&term))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|conditions| CourseFilter { conditions })
+    }
+}
+
+impl TryFrom<&str> for CourseFilter {
+    type Error = QueryError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+impl fmt::Display for CourseFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let terms: Vec<String> = self.conditions.iter().map(|c| c.to_string()).collect();
+        f.write_str(&terms.join(" "))
+    }
+}
+
+fn invalid(message: String) -> QueryError {
+    QueryError::InputError(message)
+}
+
+/// Split on whitespace outside double quotes, dropping the quotes and unescaping inside them.
+fn tokenize(input: &str) -> Result<Vec<String>, QueryError> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quoted => match chars.next() {
+                Some(c) => term.push(c),
+                None => break,
+            },
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    terms.push(std::mem::take(&mut term));
+                    started = false;
+                }
+            }
+            c => {
+                term.push(c);
+                started = true;
+            }
+        }
+    }
+
+    if quoted {
+        return Err(invalid(format!("unclosed quote in filter: {:?}", input)));
+    }
+    if started {
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+fn parse_term(term: &str) -> Result<Condition, QueryError> {
+    if let Some(rest) = term.strip_prefix('-') {
+        return Ok(Condition::Not(Box::new(parse_term(rest)?)));
+    }
+
+    if let Some(rest) = term.strip_prefix("credit") {
+        return parse_credit(term, rest);
+    }
+
+    match term {
+        "has_seats" => return Ok(Condition::HasSeats),
+        "required" => return Ok(Condition::RequireOption(String::from(REQUIRED))),
+        "elective" => return Ok(Condition::RequireOption(String::from(ELECTIVE))),
+        _ => {}
+    }
+
+    let (key, value) = term
+        .split_once(':')
+        .ok_or_else(|| invalid(format!("unknown filter term: {:?}", term)))?;
+
+    match key {
+        "day" => parse_weekday(value).map(Condition::Day),
+        "period" => value.parse().map(Condition::Period),
+        "slot" => value.parse().map(Condition::Slot),
+        "require" => Ok(Condition::RequireOption(value.to_string())),
+        "no" => Ok(Condition::CourseNo(value.to_string())),
+        "name" => Ok(Condition::Name(value.to_string())),
+        "teacher" => Ok(Condition::Teacher(value.to_string())),
+        "dimension" => Ok(Condition::Dimension(value.to_string())),
+        _ => Err(invalid(format!("unknown filter key: {:?}", key))),
+    }
+}
+
+fn parse_credit(term: &str, rest: &str) -> Result<Condition, QueryError> {
+    // longer operators first so `>=` is not read as `>`
+    let comparisons = [
+        Comparison::Ge,
+        Comparison::Le,
+        Comparison::Ne,
+        Comparison::Gt,
+        Comparison::Lt,
+        Comparison::Eq,
+    ];
+
+    comparisons
+        .into_iter()
+        .find_map(|comparison| {
+            let value = rest.strip_prefix(comparison.as_str())?;
+            Some((comparison, value.parse::<f32>().ok()?))
+        })
+        .map(|(comparison, credit)| Condition::Credit(comparison, credit))
+        .ok_or_else(|| invalid(format!("invalid credit term: {:?}", term)))
+}
+
+/// A weekday by its NTUST code (`T`) or English name (`Tue`, `Tuesday`).
+fn parse_weekday(value: &str) -> Result<Weekday, QueryError> {
+    let mut chars = value.chars();
+    if let (Some(code), None) = (chars.next(), chars.next()) {
+        if let Some(weekday) = Weekday::from_code(code) {
+            return Ok(weekday);
+        }
+    }
+
+    let names = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ];
+    let lower = value.to_ascii_lowercase();
+    Weekday::ALL
+        .into_iter()
+        .zip(names)
+        .find(|(_, name)| lower.len() >= 3 && name.starts_with(&lower))
+        .map(|(weekday, _)| weekday)
+        .ok_or_else(|| invalid(format!("invalid weekday: {:?}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::{testing::COURSES_FIXTURE, Language};
+
+    fn courses() -> Vec<CourseInfo> {
+        let mut courses: HashMap<Language, Vec<CourseInfo>> =
+            serde_json::from_str(COURSES_FIXTURE).unwrap();
+        let mut courses = courses.remove(&Language::Zh).unwrap();
+        courses.retain(|c| c.semester == "1131");
+        courses
+    }
+
+    fn course_nos(filter: &CourseFilter) -> Vec<String> {
+        filter
+            .apply(courses())
+            .into_iter()
+            .map(|c| c.course_no)
+            .collect()
+    }
+
+    #[test]
+    fn builder() {
+        let filter = CourseFilter::new()
+            .credit(Comparison::Ge, 3.0)
+            .not(Condition::Day(Weekday::Tue));
+        assert_eq!(course_nos(&filter), vec!["CS1003302"]);
+
+        assert_eq!(
+            course_nos(&CourseFilter::new().elective()),
+            vec!["GE3701301"]
+        );
+        assert_eq!(
+            course_nos(&CourseFilter::new().has_seats()),
+            vec!["CS1003302", "GE3701301"]
+        );
+        assert_eq!(
+            course_nos(&CourseFilter::new().period(Period::A).teacher("林")),
+            vec!["GE3701301"]
+        );
+        assert_eq!(course_nos(&CourseFilter::new()).len(), 3);
+    }
+
+    #[test]
+    fn parse() {
+        let filter: CourseFilter = "credit>=2 day:Tue -period:1 has_seats".parse().unwrap();
+        assert_eq!(
+            filter.conditions(),
+            &[
+                Condition::Credit(Comparison::Ge, 2.0),
+                Condition::Day(Weekday::Tue),
+                Condition::Not(Box::new(Condition::Period(Period::P1))),
+                Condition::HasSeats,
+            ]
+        );
+        // CS2006301 is on Tuesday but full
+        assert!(course_nos(&filter).is_empty());
+
+        let filter: CourseFilter = "credit<3 -required slot:wa".parse().unwrap();
+        assert_eq!(course_nos(&filter), vec!["GE3701301"]);
+
+        let filter: CourseFilter = "require:E".parse().unwrap();
+        assert_eq!(filter.to_string(), "elective");
+        assert_eq!(course_nos(&filter), vec!["GE3701301"]);
+
+        let filter: CourseFilter = "day:thursday no:cs name:\"計算機 \"".parse().unwrap();
+        assert_eq!(filter.to_string(), "day:Thu no:cs name:\"計算機 \"");
+        assert_eq!(filter.to_string().parse::<CourseFilter>().unwrap(), filter);
+
+        let filter = CourseFilter::new().name("say \"hi\"").teacher("a\\b\"");
+        assert_eq!(
+            filter.to_string(),
+            "name:\"say \\\"hi\\\"\" teacher:\"a\\\\b\\\"\""
+        );
+        assert_eq!(filter.to_string().parse::<CourseFilter>().unwrap(), filter);
+        assert_eq!(
+            "name:a\\b".parse::<CourseFilter>().unwrap(),
+            CourseFilter::new().name("a\\b")
+        );
+
+        for input in [
+            "credit>>2",
+            "day:X",
+            "day:tu",
+            "period:11",
+            "foo",
+            "size:3",
+            "name:\"open",
+            "name:\"open\\\"",
+        ] {
+            assert!(
+                input.parse::<CourseFilter>().unwrap_err().is_input(),
+                "{}",
+                input
+            );
+        }
+    }
+}
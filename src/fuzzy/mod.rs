@@ -0,0 +1,505 @@
+//! Fuzzy matching of course names and teachers, for autocomplete.
+//!
+//! A query is compared with each name in several ways and keeps the best score in `0.0..=1.0`:
+//!
+//! - exact, prefix and substring matches after normalization, which lowercases,
+//!   drops spaces and punctuation and reads numbering such as `1`, `I` and `(一)` alike;
+//!   Chinese numerals count as numbering in brackets, after `第` or after a separator,
+//!   so `(十一)`, `第二` and `微積分 三` are numbers but `統一` is not;
+//! - abbreviations, the characters of the query in order, e.g. `計程` for `計算機程式設計`;
+//! - English words, each query word matched to its best word by prefix or edit distance,
+//!   e.g. `calc 1` or `calculas` for `Calculus (I)`;
+//! - edit distance between the normalized strings, for typos and near-homographs.
+//!
+//! With the `pinyin` feature, a query without Han characters is also compared with the
+//! readings of the name, in toneless pinyin (`jisuanji`) and zhuyin (`ㄐㄧˋㄙㄨㄢˋ`).
+//! The reading table only covers characters common in course names, others are kept as written.
+
+use std::collections::HashMap;
+
+use crate::{is_cjk, CourseInfo};
+
+#[cfg(feature = "pinyin")]
+mod reading;
+
+/// Weight of a match on the readings of a name, relative to a match on its characters.
+#[cfg(feature = "pinyin")]
+const READING_WEIGHT: f32 = 0.9;
+
+/// Default minimum score of a `Matcher` candidate.
+pub const DEFAULT_MIN_SCORE: f32 = 0.5;
+
+/// Default number of candidates returned by a `Matcher`.
+pub const DEFAULT_LIMIT: usize = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MatchField {
+    CourseName,
+    CourseTeacher,
+}
+
+/// A course matching a query, with the field it matched on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate<'a> {
+    pub course: &'a CourseInfo,
+    pub field: MatchField,
+    pub score: f32,
+}
+
+/// Ranks courses by how well their name or teacher matches a query.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    min_score: f32,
+    limit: usize,
+    fields: Vec<MatchField>,
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Matcher {
+            min_score: DEFAULT_MIN_SCORE,
+            limit: DEFAULT_LIMIT,
+            fields: vec![MatchField::CourseName, MatchField::CourseTeacher],
+        }
+    }
+}
+
+impl Matcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Match on these fields only, both by default.
+    pub fn fields<I: IntoIterator<Item = MatchField>>(mut self, fields: I) -> Self {
+        self.fields = fields.into_iter().collect();
+        self
+    }
+
+    /// Best candidates first, one per course even if the course appears more than once.
+    pub fn search<'a, I>(&self, query: &str, courses: I) -> Vec<Candidate<'a>>
+    where
+        I: IntoIterator<Item = &'a CourseInfo>,
+    {
+        let query = Normalized::new(query);
+
+        let mut best: HashMap<(&str, &str), Candidate<'a>> = HashMap::new();
+        for course in courses {
+            for field in &self.fields {
+                let text = match field {
+                    MatchField::CourseName => &course.course_name,
+                    MatchField::CourseTeacher => &course.course_teacher,
+                };
+                let score = query.score(&Normalized::new(text));
+                if score < self.min_score {
+                    continue;
+                }
+
+                let candidate = Candidate {
+                    course,
+                    field: *field,
+                    score,
+                };
+                best.entry((&course.semester, &course.course_no))
+                    .and_modify(|c| {
+                        if score > c.score {
+                            *c = candidate;
+                        }
+                    })
+                    .or_insert(candidate);
+            }
+        }
+
+        let mut candidates: Vec<Candidate> = best.into_values().collect();
+        candidates.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.course.semester.cmp(&b.course.semester))
+                .then_with(|| a.course.course_no.cmp(&b.course.course_no))
+        });
+        candidates.truncate(self.limit);
+        candidates
+    }
+}
+
+/// How well `query` matches `text`, from `0.0` to `1.0`.
+pub fn score(query: &str, text: &str) -> f32 {
+    Normalized::new(query).score(&Normalized::new(text))
+}
+
+struct Normalized {
+    /// Lowercase words, each CJK character is a word of its own.
+    words: Vec<String>,
+    /// The words joined, as characters.
+    chars: Vec<char>,
+}
+
+impl Normalized {
+    fn new(text: &str) -> Self {
+        Self::from_words(words(text))
+    }
+
+    fn from_words(words: Vec<String>) -> Self {
+        let chars = words.iter().flat_map(|w| w.chars()).collect();
+        Normalized { words, chars }
+    }
+
+    fn score(&self, text: &Normalized) -> f32 {
+        let score = self.score_with(text, true);
+        #[cfg(feature = "pinyin")]
+        let score = score.max(self.reading_score(text));
+        score
+    }
+
+    /// Abbreviations are left out for readings, query initials match too many of them.
+    fn score_with(&self, text: &Normalized, abbreviations: bool) -> f32 {
+        let (q, t) = (&self.chars, &text.chars);
+        if q.is_empty() || t.is_empty() {
+            return 0.0;
+        }
+        if q == t {
+            return 1.0;
+        }
+        if t.starts_with(q) {
+            return 0.9;
+        }
+        if t.windows(q.len()).any(|w| w == q.as_slice()) {
+            return 0.8;
+        }
+
+        let abbreviation = if abbreviations && is_subsequence(q, t) {
+            0.6 + 0.2 * q.len() as f32 / t.len() as f32
+        } else {
+            0.0
+        };
+
+        [abbreviation, self.word_score(text), 0.8 * similarity(q, t)]
+            .into_iter()
+            .fold(0.0, f32::max)
+    }
+
+    /// Best score against the pinyin and zhuyin readings of `text`, if the query has no Han characters.
+    #[cfg(feature = "pinyin")]
+    fn reading_score(&self, text: &Normalized) -> f32 {
+        if self.chars.iter().any(|c| is_han(*c)) {
+            return 0.0;
+        }
+
+        let mut pinyin = Vec::with_capacity(text.words.len());
+        let mut zhuyin = Vec::with_capacity(text.words.len());
+        for word in &text.words {
+            match reading::pinyin(word).and_then(|p| Some((p, reading::zhuyin(p)?))) {
+                Some((p, z)) => {
+                    pinyin.push(p.to_string());
+                    // zhuyin symbols are CJK, each is a word as in the query
+                    zhuyin.extend(z.chars().map(String::from));
+                }
+                None => {
+                    pinyin.push(word.clone());
+                    zhuyin.push(word.clone());
+                }
+            }
+        }
+
+        [pinyin, zhuyin]
+            .into_iter()
+            .map(|words| READING_WEIGHT * self.score_with(&Normalized::from_words(words), false))
+            .fold(0.0, f32::max)
+    }
+
+    /// Average over the query words of their best match, by prefix or edit distance.
+    fn word_score(&self, text: &Normalized) -> f32 {
+        let words: Vec<&String> = self.words.iter().filter(|w| !is_cjk_word(w)).collect();
+        if words.is_empty() {
+            return 0.0;
+        }
+
+        let total: f32 = words
+            .iter()
+            .map(|word| {
+                let word: Vec<char> = word.chars().collect();
+                text.words
+                    .iter()
+                    .map(|other| {
+                        let other: Vec<char> = other.chars().collect();
+                        if other == word {
+                            1.0
+                        } else if word.len() >= 2 && other.starts_with(&word) {
+                            0.9
+                        } else {
+                            match similarity(&word, &other) {
+                                s if s >= 0.75 => s,
+                                _ => 0.0,
+                            }
+                        }
+                    })
+                    .fold(0.0, f32::max)
+            })
+            .sum();
+
+        0.9 * total / words.len() as f32
+    }
+}
+
+fn is_cjk_word(word: &str) -> bool {
+    word.chars().all(is_cjk)
+}
+
+/// CJK characters other than kana, bopomofo and hangul.
+#[cfg(feature = "pinyin")]
+fn is_han(c: char) -> bool {
+    is_cjk(c) && !matches!(c as u32, 0x3040..=0x312F | 0xAC00..=0xD7A3)
+}
+
+const ROMAN: [&str; 10] = ["i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix", "x"];
+const CJK_DIGITS: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+fn is_cjk_numeral(c: char) -> bool {
+    c == '十' || CJK_DIGITS.contains(&c)
+}
+
+/// Value of a Chinese numeral from `一` to `九十九`.
+fn cjk_number(numeral: &[char]) -> Option<usize> {
+    let digit = |c: &char| CJK_DIGITS.iter().position(|d| d == c).map(|n| n + 1);
+    match numeral {
+        [d] => digit(d).or((*d == '十').then_some(10)),
+        ['十', d] => Some(10 + digit(d)?),
+        [d, '十'] => Some(10 * digit(d)?),
+        [d, '十', e] => Some(10 * digit(d)? + digit(e)?),
+        _ => None,
+    }
+}
+
+/// Rewrite Chinese numbering as digits, e.g. `(一)`, `（十一）`, `第二` or a trailing ` 三`.
+fn numbering(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let numeral_len = |from: usize| {
+        chars[from..]
+            .iter()
+            .take_while(|c| is_cjk_numeral(**c))
+            .count()
+    };
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let start = match chars[i] {
+            '(' | '（' | '第' => i + 1,
+            _ => {
+                out.push(chars[i]);
+                i += 1;
+                continue;
+            }
+        };
+        let end = start + numeral_len(start);
+        let closed = chars[i] == '第' || matches!(chars.get(end), Some(')' | '）'));
+        match cjk_number(&chars[start..end]) {
+            Some(n) if closed => {
+                out.push_str(&format!(" {} ", n));
+                i = end + usize::from(chars[i] != '第');
+            }
+            _ => {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+
+    // a numeral at the end only counts after a separator, `統一` is a word
+    let trailing: Vec<char> = out
+        .chars()
+        .rev()
+        .take_while(|c| is_cjk_numeral(*c))
+        .collect();
+    let stem = &out[..out.len() - trailing.iter().map(|c| c.len_utf8()).sum::<usize>()];
+    if stem.ends_with([' ', '-', '_']) {
+        let numeral: Vec<char> = trailing.into_iter().rev().collect();
+        if let Some(n) = cjk_number(&numeral) {
+            return format!("{} {}", stem, n);
+        }
+    }
+    out
+}
+
+fn words(text: &str) -> Vec<String> {
+    let text = numbering(&text.trim().to_lowercase());
+
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        // zhuyin tone marks, which are letters to `is_alphanumeric`
+        if matches!(c, 'ˊ' | 'ˇ' | 'ˋ' | '˙') {
+            continue;
+        }
+        if c.is_alphanumeric() && !is_cjk(c) {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if is_cjk(c) {
+            words.push(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+        .into_iter()
+        .map(|w| match ROMAN.iter().position(|r| *r == w) {
+            Some(n) => (n + 1).to_string(),
+            None => w,
+        })
+        .collect()
+}
+
+fn is_subsequence(needle: &[char], haystack: &[char]) -> bool {
+    let mut haystack = haystack.iter();
+    needle.iter().all(|c| haystack.any(|h| h == c))
+}
+
+/// One minus the edit distance over the longer length.
+fn similarity(a: &[char], b: &[char]) -> f32 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f32 / longest as f32
+}
+
+/// Levenshtein distance.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course(course_no: &str, name: &str, teacher: &str) -> CourseInfo {
+        CourseInfo {
+            semester: String::from("1131"),
+            course_no: String::from(course_no),
+            course_name: String::from(name),
+            course_teacher: String::from(teacher),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scores() {
+        assert_eq!(edit_distance(&['a', 'b', 'c'], &['a', 'c']), 1);
+        assert_eq!(
+            edit_distance(
+                &"kitten".chars().collect::<Vec<_>>(),
+                &"sitting".chars().collect::<Vec<_>>()
+            ),
+            3
+        );
+
+        assert_eq!(score("calculus 1", "Calculus (I)"), 1.0);
+        assert_eq!(score("日本語文1", "日本語文(一)"), 1.0);
+        assert_eq!(score("計算機", "計算機程式設計"), 0.9);
+        assert!(score("計程", "計算機程式設計") > 0.6);
+        assert!(score("calculas", "Calculus (I)") > 0.6);
+        assert!(score("calc 1", "Calculus (I)") > score("calc 1", "Calculus (II)"));
+        assert!(score("式機", "計算機程式設計") < DEFAULT_MIN_SCORE);
+        assert_eq!(score("", "Calculus"), 0.0);
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(words("微積分(一)"), vec!["微", "積", "分", "1"]);
+        assert_eq!(
+            words("專題研究（十一）"),
+            vec!["專", "題", "研", "究", "11"]
+        );
+        assert_eq!(words("物理第二十三講"), vec!["物", "理", "23", "講"]);
+        assert_eq!(words("微積分 三"), vec!["微", "積", "分", "3"]);
+        assert_eq!(words("統一"), vec!["統", "一"]);
+        assert_eq!(words("一(甲)"), vec!["一", "甲"]);
+    }
+
+    #[cfg(feature = "pinyin")]
+    #[test]
+    fn readings() {
+        assert_eq!(score("jisuanjichengshisheji", "計算機程式設計"), 0.9);
+        assert!(score("jisuanji", "計算機程式設計") > 0.8);
+        assert!(score("ji suan ji", "計算機程式設計") > 0.8);
+        assert!(score("ㄐㄧˋㄙㄨㄢˋㄐㄧ", "計算機程式設計") > 0.8);
+        assert!(score("weijifen 1", "微積分(一)") > score("weijifen 1", "微積分(二)"));
+        // initials are not enough, and Han queries are not compared with readings
+        assert!(score("jsj", "計算機程式設計") < DEFAULT_MIN_SCORE);
+        assert_eq!(score("機", "王小明"), 0.0);
+        // even when the character has no reading
+        let text = Normalized::new("王小明");
+        assert_eq!(Normalized::new("龘 wangxiaoming").reading_score(&text), 0.0);
+
+        let courses = vec![
+            course("CS1003302", "計算機程式設計", "金台齡"),
+            course("MA1001301", "微積分(一)", "王小明"),
+        ];
+        let candidates = Matcher::new().search("wang xiao ming", &courses);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].field, MatchField::CourseTeacher);
+    }
+
+    #[test]
+    fn matcher() {
+        let courses = vec![
+            course("MA1001301", "微積分(一)", "王小明"),
+            course("MA1002301", "微積分(二)", "王小明"),
+            course("CS1003302", "計算機程式設計", "金台齡"),
+            // another time slot group of the same course
+            course("CS1003302", "計算機程式設計", "金台齡"),
+            course("EN1001301", "Calculus (I)", "John Smith"),
+            course("EN1002301", "Calculus (II)", "John Smith"),
+        ];
+
+        let candidates = Matcher::new().search("計程", &courses);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].course.course_no, "CS1003302");
+
+        let candidates = Matcher::new().search("calculus 1", &courses);
+        assert_eq!(candidates[0].course.course_no, "EN1001301");
+        assert_eq!(candidates[1].course.course_no, "EN1002301");
+
+        let candidates = Matcher::new().search("微積分 一", &courses);
+        assert_eq!(candidates[0].course.course_no, "MA1001301");
+        assert_eq!(candidates[0].score, 1.0);
+
+        let candidates = Matcher::new().search("smith", &courses);
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates
+            .iter()
+            .all(|c| c.field == MatchField::CourseTeacher));
+
+        let candidates = Matcher::new()
+            .fields([MatchField::CourseName])
+            .search("金台齡", &courses);
+        assert!(candidates.is_empty());
+
+        assert_eq!(Matcher::new().limit(1).search("微積分", &courses).len(), 1);
+    }
+}
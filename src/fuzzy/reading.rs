@@ -0,0 +1,388 @@
+//! Mandarin readings of common course name characters, for pinyin and zhuyin queries.
+//!
+//! Each character has one toneless reading, the most common one in course names,
+//! e.g. `行` is `xing` and `樂` is `yue`. Zhuyin is spelled out from the pinyin.
+
+use std::{collections::HashMap, sync::OnceLock};
+
+/// Toneless pinyin syllables with the characters read that way, `v` stands for `ü`.
+const SYLLABLES: &[(&str, &str)] = &[
+    ("a", "阿"),
+    ("ai", "愛"),
+    ("an", "安案"),
+    ("ao", "奧"),
+    ("ba", "八巴"),
+    ("bai", "百白"),
+    ("ban", "班半版辦板"),
+    ("bao", "報保寶包"),
+    ("bei", "北備背"),
+    ("ben", "本"),
+    ("bi", "比必筆畢"),
+    ("bian", "變編邊便"),
+    ("biao", "表標"),
+    ("bie", "別"),
+    ("bing", "並病"),
+    ("bo", "博波播"),
+    ("bu", "不部布步補"),
+    ("cai", "材財才採"),
+    ("can", "參"),
+    ("cao", "操"),
+    ("ce", "測策側"),
+    ("cha", "查差"),
+    ("chan", "產"),
+    ("chang", "場常長"),
+    ("chao", "超"),
+    ("che", "車"),
+    ("chen", "陳"),
+    ("cheng", "程成城承乘"),
+    ("chi", "持池尺"),
+    ("chong", "衝充"),
+    ("chu", "出初處礎"),
+    ("chuan", "傳川"),
+    ("chuang", "創窗"),
+    ("ci", "磁詞此次"),
+    ("cong", "從"),
+    ("cun", "存"),
+    ("da", "大達答"),
+    ("dai", "代帶待"),
+    ("dan", "單"),
+    ("dang", "當"),
+    ("dao", "導道到"),
+    ("de", "德得的"),
+    ("deng", "等燈"),
+    ("di", "地第低底帝"),
+    ("dian", "電點典店"),
+    ("diao", "調"),
+    ("ding", "定"),
+    ("dong", "動東冬"),
+    ("du", "度讀獨"),
+    ("duan", "段端"),
+    ("dui", "對"),
+    ("duo", "多"),
+    ("e", "額"),
+    ("er", "二兒而爾"),
+    ("fa", "法發"),
+    ("fan", "反範"),
+    ("fang", "方房放防"),
+    ("fei", "非費"),
+    ("fen", "分"),
+    ("feng", "風封"),
+    ("fu", "服復輔附負複福"),
+    ("gai", "概改"),
+    ("gan", "感"),
+    ("gang", "鋼"),
+    ("gao", "高"),
+    ("ge", "格個歌革"),
+    ("gong", "工公共功供攻"),
+    ("gou", "構購"),
+    ("gu", "古故固"),
+    ("guan", "管關觀館"),
+    ("guang", "光廣"),
+    ("gui", "規"),
+    ("guo", "國過"),
+    ("hai", "海"),
+    ("han", "漢函"),
+    ("hang", "航"),
+    ("he", "合和核何"),
+    ("hou", "後"),
+    ("hu", "互護"),
+    ("hua", "化畫華話"),
+    ("huan", "環換"),
+    ("hui", "會繪回惠"),
+    ("huo", "活火"),
+    ("ji", "計機積基級際技記集極紀幾擊績即濟"),
+    ("jia", "加家價架"),
+    ("jian", "建間件簡檢見鑑"),
+    ("jiang", "將講"),
+    ("jiao", "教交較"),
+    ("jie", "結介界解接節"),
+    ("jin", "進金近今"),
+    ("jing", "經精境競景"),
+    ("jiu", "究就九"),
+    ("ju", "據具局"),
+    ("juan", "卷"),
+    ("jue", "決"),
+    ("jun", "均"),
+    ("kai", "開"),
+    ("ke", "科課可客"),
+    ("kong", "控空"),
+    ("kou", "口"),
+    ("kuai", "快"),
+    ("lao", "勞"),
+    ("lei", "類"),
+    ("li", "理力利歷立禮例李"),
+    ("lian", "連練"),
+    ("liang", "量兩"),
+    ("liao", "料療"),
+    ("lin", "林"),
+    ("ling", "領零齡"),
+    ("liu", "流六"),
+    ("lu", "路錄陸"),
+    ("lun", "論輪"),
+    ("luo", "邏"),
+    ("lv", "律綠"),
+    ("ma", "馬碼"),
+    ("mei", "美"),
+    ("men", "門們"),
+    ("mi", "密"),
+    ("mian", "面"),
+    ("min", "民"),
+    ("ming", "明名銘"),
+    ("mo", "模"),
+    ("mu", "目"),
+    ("nan", "南"),
+    ("nao", "腦"),
+    ("nei", "內"),
+    ("neng", "能"),
+    ("nian", "年"),
+    ("nong", "農"),
+    ("pin", "品"),
+    ("ping", "平評"),
+    ("pu", "普"),
+    ("qi", "期器企其氣汽起七"),
+    ("qian", "前"),
+    ("qing", "情清"),
+    ("qiu", "球"),
+    ("qu", "區曲"),
+    ("quan", "全權"),
+    ("que", "確"),
+    ("ren", "人認"),
+    ("ri", "日"),
+    ("rong", "容"),
+    ("ru", "入"),
+    ("ruan", "軟"),
+    ("san", "三"),
+    ("se", "色"),
+    ("shang", "商上"),
+    ("she", "設社"),
+    ("shen", "深身神"),
+    ("sheng", "生聲"),
+    ("shi", "式時實識史市世事師視試示使施室適十"),
+    ("shou", "手"),
+    ("shu", "數術書輸"),
+    ("shui", "水"),
+    ("si", "四思司私"),
+    ("su", "速"),
+    ("suan", "算"),
+    ("tai", "台臺"),
+    ("te", "特"),
+    ("ti", "體題提"),
+    ("tian", "天"),
+    ("tong", "統通"),
+    ("tou", "投"),
+    ("tu", "圖"),
+    ("wai", "外"),
+    ("wang", "網王"),
+    ("wei", "微位維"),
+    ("wen", "文"),
+    ("wu", "物務五"),
+    ("xi", "系習析息西"),
+    ("xia", "下"),
+    ("xian", "線現限顯"),
+    ("xiang", "相向項"),
+    ("xiao", "效小"),
+    ("xie", "寫協"),
+    ("xin", "信心新"),
+    ("xing", "行性型形"),
+    ("xu", "序需"),
+    ("xuan", "選"),
+    ("xue", "學"),
+    ("yan", "研驗言"),
+    ("yang", "樣洋"),
+    ("yao", "要"),
+    ("ye", "業"),
+    ("yi", "一易醫藝意儀議"),
+    ("yin", "音引印"),
+    ("ying", "應英影營"),
+    ("yong", "用"),
+    ("you", "有優"),
+    ("yu", "語與域育"),
+    ("yuan", "原元源員院"),
+    ("yue", "樂月約"),
+    ("yun", "運雲"),
+    ("zao", "造"),
+    ("zhan", "展戰"),
+    ("zhen", "真"),
+    ("zheng", "整正證政"),
+    ("zhi", "制智治知製質值職織"),
+    ("zhong", "中重"),
+    ("zhu", "主註助"),
+    ("zhuan", "專"),
+    ("zhuang", "裝"),
+    ("zi", "自資子字"),
+    ("zong", "總"),
+    ("zu", "組"),
+    ("zui", "最"),
+    ("zuo", "作"),
+];
+
+const INITIALS: [(&str, &str); 21] = [
+    ("zh", "ㄓ"),
+    ("ch", "ㄔ"),
+    ("sh", "ㄕ"),
+    ("b", "ㄅ"),
+    ("p", "ㄆ"),
+    ("m", "ㄇ"),
+    ("f", "ㄈ"),
+    ("d", "ㄉ"),
+    ("t", "ㄊ"),
+    ("n", "ㄋ"),
+    ("l", "ㄌ"),
+    ("g", "ㄍ"),
+    ("k", "ㄎ"),
+    ("h", "ㄏ"),
+    ("j", "ㄐ"),
+    ("q", "ㄑ"),
+    ("x", "ㄒ"),
+    ("r", "ㄖ"),
+    ("z", "ㄗ"),
+    ("c", "ㄘ"),
+    ("s", "ㄙ"),
+];
+
+const FINALS: [(&str, &str); 36] = [
+    ("a", "ㄚ"),
+    ("o", "ㄛ"),
+    ("e", "ㄜ"),
+    ("ai", "ㄞ"),
+    ("ei", "ㄟ"),
+    ("ao", "ㄠ"),
+    ("ou", "ㄡ"),
+    ("an", "ㄢ"),
+    ("en", "ㄣ"),
+    ("ang", "ㄤ"),
+    ("eng", "ㄥ"),
+    ("er", "ㄦ"),
+    ("ong", "ㄨㄥ"),
+    ("i", "ㄧ"),
+    ("ia", "ㄧㄚ"),
+    ("ie", "ㄧㄝ"),
+    ("iao", "ㄧㄠ"),
+    ("iu", "ㄧㄡ"),
+    ("ian", "ㄧㄢ"),
+    ("in", "ㄧㄣ"),
+    ("iang", "ㄧㄤ"),
+    ("ing", "ㄧㄥ"),
+    ("iong", "ㄩㄥ"),
+    ("u", "ㄨ"),
+    ("ua", "ㄨㄚ"),
+    ("uo", "ㄨㄛ"),
+    ("uai", "ㄨㄞ"),
+    ("ui", "ㄨㄟ"),
+    ("uan", "ㄨㄢ"),
+    ("un", "ㄨㄣ"),
+    ("uang", "ㄨㄤ"),
+    ("ueng", "ㄨㄥ"),
+    ("v", "ㄩ"),
+    ("ve", "ㄩㄝ"),
+    ("van", "ㄩㄢ"),
+    ("vn", "ㄩㄣ"),
+];
+
+/// Pinyin of a one-character word, if the character is in the table.
+pub(super) fn pinyin(word: &str) -> Option<&'static str> {
+    static TABLE: OnceLock<HashMap<char, &'static str>> = OnceLock::new();
+
+    let mut chars = word.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    TABLE
+        .get_or_init(|| {
+            SYLLABLES
+                .iter()
+                .flat_map(|(syllable, chars)| chars.chars().map(|c| (c, *syllable)))
+                .collect()
+        })
+        .get(&c)
+        .copied()
+}
+
+/// Spell a toneless pinyin syllable in zhuyin, e.g. `xue` as `ㄒㄩㄝ`.
+pub(super) fn zhuyin(pinyin: &str) -> Option<String> {
+    // y- and w- only mark a syllable without initial, spell out their medial
+    let spelled = match pinyin {
+        "yi" | "yin" | "ying" => pinyin[1..].to_string(),
+        "you" => String::from("iu"),
+        "wu" => String::from("u"),
+        "wei" => String::from("ui"),
+        "wen" => String::from("un"),
+        _ => match pinyin.strip_prefix("yu") {
+            Some(rest) => format!("v{}", rest),
+            None => match pinyin.strip_prefix('y').or(pinyin.strip_prefix('w')) {
+                Some(rest) if pinyin.starts_with('y') => format!("i{}", rest),
+                Some(rest) => format!("u{}", rest),
+                None => pinyin.to_string(),
+            },
+        },
+    };
+
+    let (initial, mut rime) = INITIALS
+        .iter()
+        .find(|(latin, _)| spelled.starts_with(latin))
+        .map_or(("", spelled.as_str()), |(latin, _)| {
+            (*latin, &spelled[latin.len()..])
+        });
+
+    // `ju` is `jü`, `zhi` has no vowel of its own
+    let umlaut;
+    if matches!(initial, "j" | "q" | "x") && rime.starts_with('u') {
+        umlaut = format!("v{}", &rime[1..]);
+        rime = &umlaut;
+    }
+    if rime == "i" && matches!(initial, "zh" | "ch" | "sh" | "r" | "z" | "c" | "s") {
+        rime = "";
+    }
+
+    let mut out = String::new();
+    if let Some((_, symbol)) = INITIALS.iter().find(|(latin, _)| *latin == initial) {
+        out.push_str(symbol);
+    }
+    if !rime.is_empty() {
+        out.push_str(FINALS.iter().find(|(latin, _)| *latin == rime)?.1);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table() {
+        let mut seen = HashMap::new();
+        for (syllable, chars) in SYLLABLES {
+            assert!(zhuyin(syllable).is_some(), "{}", syllable);
+            for c in chars.chars() {
+                assert_eq!(seen.insert(c, syllable), None, "{}", c);
+            }
+        }
+
+        assert_eq!(pinyin("學"), Some("xue"));
+        assert_eq!(pinyin("學習"), None);
+        assert_eq!(pinyin("a"), None);
+    }
+
+    #[test]
+    fn spelling() {
+        for (pinyin, expected) in [
+            ("ji", "ㄐㄧ"),
+            ("suan", "ㄙㄨㄢ"),
+            ("shi", "ㄕ"),
+            ("xue", "ㄒㄩㄝ"),
+            ("qu", "ㄑㄩ"),
+            ("lv", "ㄌㄩ"),
+            ("yi", "ㄧ"),
+            ("you", "ㄧㄡ"),
+            ("yong", "ㄩㄥ"),
+            ("yuan", "ㄩㄢ"),
+            ("wei", "ㄨㄟ"),
+            ("wang", "ㄨㄤ"),
+            ("er", "ㄦ"),
+            ("zhong", "ㄓㄨㄥ"),
+        ] {
+            assert_eq!(zhuyin(pinyin).as_deref(), Some(expected), "{}", pinyin);
+        }
+        assert_eq!(zhuyin("xyz"), None);
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    fmt::{self, Write},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    CourseDetails, CourseInfo, Period, QueryError, Semester, Term, TimeSlot, Weekday,
+    UTC_OFFSET_HOURS,
+};
+
+/// Time zone of every event, NTUST is in Taipei (UTC+8, no daylight saving).
+pub const TIMEZONE: &str = "Asia/Taipei";
+
+/// Number of weeks in a regular term.
+pub const DEFAULT_WEEKS: u32 = 18;
+/// Number of weeks in a summer term.
+pub const DEFAULT_SUMMER_WEEKS: u32 = 8;
+
+const PRODUCT_ID: &str = "-//NTUST-Hack//q//EN";
+const LINE_LIMIT: usize = 75;
+
+/// A calendar date in the Gregorian calendar.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u8, day: u8) -> Option<Self> {
+        let date = Date { year, month, day };
+        ((1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month)).then_some(date)
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        Weekday::ALL[(self.days_since_epoch() + 3).rem_euclid(7) as usize]
+    }
+
+    pub fn add_days(&self, days: i64) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() + days)
+    }
+
+    // Howard Hinnant's days_from_civil
+    fn days_since_epoch(&self) -> i64 {
+        let year = self.year as i64 - (self.month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let yoe = year - era * 400;
+        let month = self.month as i64;
+        let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+
+    fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+        Date { year, month, day }
+    }
+}
+
+impl FromStr for Date {
+    type Err = QueryError;
+
+    /// Parse an ISO 8601 date such as `2024-09-09`.
+    fn from_str(input: &str) -> Result<Date, Self::Err> {
+        let invalid = || QueryError::InputError(format!("invalid date: {:?}", input));
+
+        let mut parts = input.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let year = next()?.parse().map_err(|_| invalid())?;
+        let month = next()?.parse().map_err(|_| invalid())?;
+        let day = next()?.parse().map_err(|_| invalid())?;
+
+        Date::new(year, month, day).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// One weekly recurring block of consecutive periods.
+#[derive(Debug, Clone)]
+struct Event {
+    uid: String,
+    summary: String,
+    location: Option<String>,
+    description: String,
+    weekday: Weekday,
+    first: Period,
+    last: Period,
+}
+
+/// An RFC 5545 calendar of weekly class meetings for one semester.
+///
+/// Consecutive periods of a course on the same day become a single event,
+/// repeated every week from `first_day` for the given number of weeks.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    semester: Semester,
+    first_day: Date,
+    weeks: u32,
+    events: Vec<Event>,
+}
+
+impl Calendar {
+    /// `first_day` is the first day of classes, the week it falls in counts as week one.
+    pub fn new(semester: Semester, first_day: Date) -> Self {
+        Calendar {
+            semester,
+            first_day,
+            weeks: match semester.term {
+                Term::Summer => DEFAULT_SUMMER_WEEKS,
+                Term::Fall | Term::Spring => DEFAULT_WEEKS,
+            },
+            events: Vec::new(),
+        }
+    }
+
+    pub fn weeks(mut self, weeks: u32) -> Self {
+        self.weeks = weeks;
+        self
+    }
+
+    pub fn add(&mut self, course: &CourseInfo) -> Result<(), QueryError> {
+        self.add_events(course, None)
+    }
+
+    /// Add a course, using the details for a richer event description.
+    pub fn add_with_details(
+        &mut self,
+        course: &CourseInfo,
+        details: &CourseDetails,
+    ) -> Result<(), QueryError> {
+        self.add_events(course, Some(details))
+    }
+
+    fn add_events(
+        &mut self,
+        course: &CourseInfo,
+        details: Option<&CourseDetails>,
+    ) -> Result<(), QueryError> {
+        let slots = course.time_slots()?;
+
+        let mut description = format!("{} {}", course.course_no, course.course_teacher);
+        if let Some(url) = details.and_then(|d| d.course_url.as_ref()) {
+            description.push('\n');
+            description.push_str(url);
+        }
+
+        let location = course
+            .class_room_no
+            .as_ref()
+            .or(details.and_then(|d| d.class_room_no.as_ref()))
+            .filter(|room| !room.is_empty())
+            .cloned();
+
+        for (first, last) in blocks(&slots) {
+            self.events.push(Event {
+                uid: format!(
+                    "{}-{}-{}@q.ntust.edu.tw",
+                    self.semester, course.course_no, first
+                ),
+                summary: course.course_name.clone(),
+                location: location.clone(),
+                description: description.clone(),
+                weekday: first.weekday,
+                first: first.period,
+                last: last.period,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Date of the first occurrence of `weekday` in the teaching weeks.
+    fn first_occurrence(&self, weekday: Weekday) -> Date {
+        let offset =
+            (weekday.index() as i64 - self.first_day.weekday().index() as i64).rem_euclid(7);
+        self.first_day.add_days(offset)
+    }
+
+    /// Last day of the last teaching week.
+    fn last_day(&self) -> Date {
+        let week_start = self
+            .first_day
+            .add_days(-(self.first_day.weekday().index() as i64));
+        week_start.add_days(self.weeks as i64 * 7 - 1)
+    }
+}
+
+impl fmt::Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stamp = utc_timestamp(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64,
+        );
+
+        // the end of the last day in Taipei, as UTC as required for UNTIL
+        let last_day = self.last_day();
+        let until =
+            utc_timestamp((last_day.days_since_epoch() + 1) * 86400 - UTC_OFFSET_HOURS * 3600 - 1);
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:{}", PRODUCT_ID),
+            "CALSCALE:GREGORIAN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+            format!("X-WR-CALNAME:NTUST {}", self.semester),
+            format!("X-WR-TIMEZONE:{}", TIMEZONE),
+            "BEGIN:VTIMEZONE".to_string(),
+            format!("TZID:{}", TIMEZONE),
+            "BEGIN:STANDARD".to_string(),
+            "DTSTART:19700101T000000".to_string(),
+            "TZOFFSETFROM:+0800".to_string(),
+            "TZOFFSETTO:+0800".to_string(),
+            "TZNAME:CST".to_string(),
+            "END:STANDARD".to_string(),
+            "END:VTIMEZONE".to_string(),
+        ];
+
+        for event in &self.events {
+            let date = self.first_occurrence(event.weekday);
+            if date > last_day {
+                continue;
+            }
+
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}", event.uid));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!(
+                "DTSTART;TZID={}:{}",
+                TIMEZONE,
+                local_timestamp(date, event.first.start_time())
+            ));
+            lines.push(format!(
+                "DTEND;TZID={}:{}",
+                TIMEZONE,
+                local_timestamp(date, event.last.end_time())
+            ));
+            lines.push(format!("RRULE:FREQ=WEEKLY;UNTIL={}", until));
+            lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+            if let Some(location) = &event.location {
+                lines.push(format!("LOCATION:{}", escape(location)));
+            }
+            lines.push(format!("DESCRIPTION:{}", escape(&event.description)));
+            lines.push("END:VEVENT".to_string());
+        }
+
+        lines.push("END:VCALENDAR".to_string());
+
+        for line in lines {
+            write_folded(f, &line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Group sorted slots into runs of consecutive periods on the same day.
+fn blocks(slots: &[TimeSlot]) -> Vec<(TimeSlot, TimeSlot)> {
+    let mut blocks: Vec<(TimeSlot, TimeSlot)> = Vec::new();
+
+    for slot in slots {
+        match blocks.last_mut() {
+            Some((_, last))
+                if last.weekday == slot.weekday
+                    && last.period.index() + 1 == slot.period.index() =>
+            {
+                *last = *slot
+            }
+            _ => blocks.push((*slot, *slot)),
+        }
+    }
+
+    blocks
+}
+
+fn local_timestamp(date: Date, (hour, minute): (u8, u8)) -> String {
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}00",
+        date.year, date.month, date.day, hour, minute
+    )
+}
+
+fn utc_timestamp(unix_secs: i64) -> String {
+    let date = Date::from_days_since_epoch(unix_secs.div_euclid(86400));
+    let secs = unix_secs.rem_euclid(86400);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        date.year,
+        date.month,
+        date.day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write a content line folded at 75 octets, without splitting UTF-8 characters.
+fn write_folded(f: &mut fmt::Formatter, line: &str) -> fmt::Result {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > LINE_LIMIT {
+            f.write_str("\r\n ")?;
+            // the leading space counts towards the next line
+            width = 1;
+        }
+        f.write_char(c)?;
+        width += c.len_utf8();
+    }
+    f.write_str("\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date() {
+        let date: Date = "2024-09-09".parse().unwrap();
+        assert_eq!(date.weekday(), Weekday::Mon);
+        assert_eq!(date.add_days(-9).to_string(), "2024-08-31");
+        assert_eq!(date.add_days(200).to_string(), "2025-03-28");
+        assert_eq!(Date::new(2024, 2, 29).unwrap().weekday(), Weekday::Thu);
+        assert_eq!(Date::new(1970, 1, 1).unwrap().days_since_epoch(), 0);
+
+        assert!(Date::new(2023, 2, 29).is_none());
+        assert!("2024-13-01".parse::<Date>().is_err());
+        assert!("2024-09".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(utc_timestamp(0), "19700101T000000Z");
+        assert_eq!(utc_timestamp(1726000000), "20240910T202640Z");
+    }
+
+    #[test]
+    fn calendar() {
+        let semester: Semester = "1131".parse().unwrap();
+        // a Wednesday
+        let first_day: Date = "2024-09-11".parse().unwrap();
+
+        let mut calendar = Calendar::new(semester, first_day).weeks(2);
+        calendar
+            .add(&CourseInfo {
+                course_no: String::from("CS1003302"),
+                course_name: String::from("計算機程式設計, Programming; 1"),
+                course_teacher: String::from("金台齡"),
+                class_room_no: Some(String::from("TR-313")),
+                node: Some(String::from("M1,M2,R3,RA,M4")),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let ics = calendar.to_string();
+        let unfolded = ics.replace("\r\n ", "");
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= LINE_LIMIT));
+
+        // M1-M2 is merged, M4 and RA stay separate, the first Monday is in the second week
+        assert_eq!(unfolded.matches("BEGIN:VEVENT").count(), 4);
+        assert!(unfolded.contains("DTSTART;TZID=Asia/Taipei:20240916T081000\r\n"));
+        assert!(unfolded.contains("DTEND;TZID=Asia/Taipei:20240916T100000\r\n"));
+        assert!(unfolded.contains("DTSTART;TZID=Asia/Taipei:20240912T102000\r\n"));
+        assert!(unfolded.contains("DTSTART;TZID=Asia/Taipei:20240912T182500\r\n"));
+        assert!(unfolded.contains("RRULE:FREQ=WEEKLY;UNTIL=20240922T155959Z\r\n"));
+        assert!(unfolded.contains("SUMMARY:計算機程式設計\\, Programming\\; 1\r\n"));
+        assert!(unfolded.contains("LOCATION:TR-313\r\n"));
+        assert!(unfolded.contains("UID:1131-CS1003302-M1@q.ntust.edu.tw\r\n"));
+    }
+
+    #[test]
+    fn fold() {
+        struct Line(String);
+        impl fmt::Display for Line {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write_folded(f, &self.0)
+            }
+        }
+
+        let line = format!("DESCRIPTION:{}", "計".repeat(40));
+        let folded = Line(line.clone()).to_string();
+
+        assert!(folded.split("\r\n").all(|l| l.len() <= LINE_LIMIT));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{}\r\n", line));
+    }
+}
@@ -0,0 +1,333 @@
+//! Full-text search over syllabus content.
+//!
+//! [`SearchIndex`] is an in-memory inverted index over `CourseDetails`, ranked with BM25.
+//! Latin text is split into lowercase words, runs of CJK characters into overlapping
+//! bigrams, so `深度學習` also matches `深度學習導論` without a dictionary.
+//! Each CJK character is indexed on its own as well, so one-character queries match.
+
+use std::collections::HashMap;
+
+use crate::{is_cjk, CourseDetails};
+
+/// Term frequency saturation of BM25.
+const K1: f32 = 1.2;
+/// Document length normalization of BM25.
+const B: f32 = 0.75;
+/// Weight of a term in the course name, relative to the syllabus fields.
+const NAME_WEIGHT: f32 = 3.0;
+
+/// A ranked result of `SearchIndex::search`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit<'a> {
+    pub details: &'a CourseDetails,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Document {
+    details: CourseDetails,
+    length: f32,
+    /// Terms with a posting for this document, to drop them when it is replaced.
+    terms: Vec<String>,
+}
+
+/// An inverted index of course details keyed by semester and course number.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    documents: Vec<Document>,
+    postings: HashMap<String, Vec<(usize, f32)>>,
+    keys: HashMap<(String, String), usize>,
+    total_length: f32,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index a course, replacing an earlier version of the same course.
+    ///
+    /// The name, objective, content, textbooks, notes and remarks are indexed.
+    pub fn add(&mut self, details: CourseDetails) {
+        let key = (details.semester.clone(), details.course_no.clone());
+        let replaced = self.keys.get(&key).copied();
+        if let Some(id) = replaced {
+            self.remove_postings(id);
+        }
+
+        let id = replaced.unwrap_or(self.documents.len());
+        let mut frequencies: HashMap<String, f32> = HashMap::new();
+        for (text, weight) in fields(&details) {
+            for token in split(text, true) {
+                *frequencies.entry(token).or_default() += weight;
+            }
+        }
+
+        let length = frequencies.values().sum();
+        let mut terms = Vec::with_capacity(frequencies.len());
+        for (token, frequency) in frequencies {
+            self.postings
+                .entry(token.clone())
+                .or_default()
+                .push((id, frequency));
+            terms.push(token);
+        }
+
+        self.total_length += length;
+        let document = Document {
+            details,
+            length,
+            terms,
+        };
+        match replaced {
+            Some(id) => self.documents[id] = document,
+            None => {
+                self.documents.push(document);
+                self.keys.insert(key, id);
+            }
+        }
+    }
+
+    fn remove_postings(&mut self, id: usize) {
+        let document = &self.documents[id];
+        self.total_length -= document.length;
+        for term in &document.terms {
+            if let Some(postings) = self.postings.get_mut(term) {
+                postings.retain(|(other, _)| *other != id);
+                if postings.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+    }
+
+    /// Number of indexed courses.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn get(&self, semester: &str, course_no: &str) -> Option<&CourseDetails> {
+        let id = self
+            .keys
+            .get(&(semester.to_string(), course_no.to_string()))?;
+        Some(&self.documents[*id].details)
+    }
+
+    /// Courses matching any term of `query`, best first, at most `limit` of them.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Hit<'_>> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let count = self.len() as f32;
+        let average_length = (self.total_length / count).max(1.0);
+
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        for term in terms {
+            let Some(postings) = self.postings.get(&term) else {
+                continue;
+            };
+            let df = postings.len() as f32;
+            let idf = (1.0 + (count - df + 0.5) / (df + 0.5)).ln();
+            for (id, tf) in postings {
+                let norm = K1 * (1.0 - B + B * self.documents[*id].length / average_length);
+                *scores.entry(*id).or_default() += idf * tf * (K1 + 1.0) / (tf + norm);
+            }
+        }
+
+        let mut hits: Vec<Hit> = scores
+            .into_iter()
+            .map(|(id, score)| Hit {
+                details: &self.documents[id].details,
+                score,
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.details.semester.cmp(&b.details.semester))
+                .then_with(|| a.details.course_no.cmp(&b.details.course_no))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+impl FromIterator<CourseDetails> for SearchIndex {
+    fn from_iter<I: IntoIterator<Item = CourseDetails>>(iter: I) -> Self {
+        let mut index = SearchIndex::new();
+        iter.into_iter().for_each(|details| index.add(details));
+        index
+    }
+}
+
+impl Extend<CourseDetails> for SearchIndex {
+    fn extend<I: IntoIterator<Item = CourseDetails>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|details| self.add(details));
+    }
+}
+
+fn fields(details: &CourseDetails) -> Vec<(&str, f32)> {
+    let mut fields = vec![(details.course_name.as_str(), NAME_WEIGHT)];
+    fields.extend(
+        [
+            &details.course_object,
+            &details.course_content,
+            &details.course_textbook,
+            &details.course_refbook,
+            &details.course_note,
+            &details.course_remark,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|text| (text.as_str(), 1.0)),
+    );
+    fields
+}
+
+/// Split a query into search terms.
+///
+/// Letters and digits form lowercase words, each run of CJK characters yields its
+/// bigrams, or the character itself if it stands alone. Everything else separates terms.
+pub fn tokenize(text: &str) -> Vec<String> {
+    split(text, false)
+}
+
+/// `tokenize`, plus every character of the CJK runs if `unigrams` is set.
+fn split(text: &str, unigrams: bool) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut run: Vec<char> = Vec::new();
+
+    let flush_run = |run: &mut Vec<char>, tokens: &mut Vec<String>| {
+        match run.len() {
+            0 => {}
+            1 => tokens.push(run[0].to_string()),
+            _ => {
+                tokens.extend(run.windows(2).map(|pair| pair.iter().collect::<String>()));
+                if unigrams {
+                    tokens.extend(run.iter().map(char::to_string));
+                }
+            }
+        }
+        run.clear();
+    };
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            run.push(c);
+        } else if c.is_alphanumeric() {
+            flush_run(&mut run, &mut tokens);
+            word.extend(c.to_lowercase());
+        } else {
+            flush_run(&mut run, &mut tokens);
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+        }
+    }
+
+    flush_run(&mut run, &mut tokens);
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(course_no: &str, name: &str, content: &str) -> CourseDetails {
+        CourseDetails {
+            semester: String::from("1131"),
+            course_no: String::from(course_no),
+            course_name: String::from(name),
+            course_content: Some(String::from(content)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize("Machine-Learning 深度學習, GPU學"),
+            vec!["machine", "learning", "深度", "度學", "學習", "gpu", "學"]
+        );
+        assert_eq!(tokenize("日本語文(一)"), vec!["日本", "本語", "語文", "一"]);
+        assert!(tokenize(" ,. ").is_empty());
+        assert_eq!(
+            split("深度學習 AI", true),
+            vec!["深度", "度學", "學習", "深", "度", "學", "習", "ai"]
+        );
+    }
+
+    #[test]
+    fn ranking() {
+        let mut index: SearchIndex = [
+            details(
+                "CS5001301",
+                "深度學習",
+                "Neural networks and deep learning, 卷積神經網路",
+            ),
+            details(
+                "CS5002301",
+                "機器學習",
+                "Machine learning: regression, SVM, and an introduction to 深度學習",
+            ),
+            details("CS1003302", "計算機程式設計", "C programming"),
+        ]
+        .into_iter()
+        .collect();
+
+        let hits = index.search("machine learning 深度學習", 10);
+        let mut course_nos: Vec<&str> = hits.iter().map(|h| h.details.course_no.as_str()).collect();
+        course_nos.sort();
+        assert_eq!(course_nos, vec!["CS5001301", "CS5002301"]);
+        assert!(hits[0].score >= hits[1].score);
+
+        // a match in the name outweighs one in the content
+        let hits = index.search("深度學習", 10);
+        assert_eq!(hits[0].details.course_no, "CS5001301");
+        assert!(hits[0].score > hits[1].score);
+        assert_eq!(
+            index.search("機器學習", 10)[0].details.course_no,
+            "CS5002301"
+        );
+        assert_eq!(index.search("programming", 1).len(), 1);
+        assert!(index.search("compiler", 10).is_empty());
+
+        // a single CJK character matches the courses containing it
+        let hits = index.search("機", 10);
+        let mut course_nos: Vec<&str> = hits.iter().map(|h| h.details.course_no.as_str()).collect();
+        course_nos.sort();
+        assert_eq!(course_nos, vec!["CS1003302", "CS5002301"]);
+
+        // replacing a course drops its old content and postings
+        index.add(details("CS1003302", "計算機程式設計", "Python"));
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.documents.len(), 3);
+        assert!(index.search("programming", 10).is_empty());
+        assert!(!index.postings.contains_key("programming"));
+        assert_eq!(index.search("python", 10).len(), 1);
+        assert_eq!(
+            index
+                .get("1131", "CS1003302")
+                .unwrap()
+                .course_content
+                .as_deref(),
+            Some("Python")
+        );
+    }
+}
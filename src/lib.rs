@@ -1,16 +1,45 @@
 mod async_impl;
 pub use self::async_impl::*;
 pub mod blocking;
+pub mod cache;
+pub mod cassette;
+mod time_slot;
+pub use self::time_slot::*;
+pub mod timetable;
+pub use self::timetable::Timetable;
+mod semester;
+pub use self::semester::*;
+mod course_no;
+pub use self::course_no::*;
+mod course;
+pub use self::course::*;
+pub mod diff;
+pub mod enrollment;
+pub mod filter;
+pub use self::filter::CourseFilter;
+pub mod fuzzy;
+pub mod ical;
+#[cfg(feature = "index")]
+pub mod index;
+pub mod source;
+pub use self::source::CourseSource;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
-use serde::{Deserialize, Serialize};
-use serde_with::{serde_as, BoolFromInt};
-use std::{collections::HashMap, fmt, str::FromStr, time::Duration};
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_with::{serde_as, BoolFromInt, DisplayFromStr, PickFirst};
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc, time::Duration};
 
 pub const DEFAULT_USER_AGENT: &'static str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36";
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
 pub const DEFAULT_API_URL: &'static str = "https://querycourse.ntust.edu.tw/querycourse/api/";
+/// Offset of Asia/Taipei from UTC.
+pub(crate) const UTC_OFFSET_HOURS: i64 = 8;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Language {
     Zh,
@@ -38,147 +67,262 @@ impl Language {
     }
 }
 
+/// A search hit from the `courses` endpoint.
+///
+/// Serializes to the canonical camelCase format and deserializes from either that
+/// or the API's PascalCase, so saved snapshots load back into the same type.
+/// Numbers the API sends as strings are accepted both as strings and as numbers.
 #[serde_as]
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "PascalCase"))]
 pub struct CourseInfo {
+    #[serde(alias = "semester")]
     pub semester: String,
+    #[serde(alias = "courseNo")]
     pub course_no: String,
+    #[serde(alias = "courseName")]
     pub course_name: String,
+    #[serde(alias = "courseTeacher")]
     pub course_teacher: String,
+    #[serde(alias = "dimension")]
     pub dimension: String,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "creditPoint")]
     pub credit_point: f32,
+    #[serde(alias = "requireOption")]
     pub require_option: String,
+    #[serde(alias = "allYear")]
     pub all_year: String,
+    #[serde(alias = "chooseStudent")]
     pub choose_student: i32,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "restrict1")]
     pub restrict1: i32,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "restrict2")]
     pub restrict2: i32,
+    #[serde(alias = "threeStudent")]
     pub three_student: i32,
+    #[serde(alias = "allStudent")]
     pub all_student: i32,
-    #[serde(rename(serialize = "ntuRestrict", deserialize = "NTURestrict"))]
+    #[serde(
+        rename(serialize = "ntuRestrict", deserialize = "NTURestrict"),
+        alias = "ntuRestrict"
+    )]
     #[serde_as(deserialize_as = "serde_with::DefaultOnError")]
     pub nturestrict: i32,
-    #[serde(rename(serialize = "ntnuRestrict", deserialize = "NTNURestrict"))]
+    #[serde(
+        rename(serialize = "ntnuRestrict", deserialize = "NTNURestrict"),
+        alias = "ntnuRestrict"
+    )]
     #[serde_as(deserialize_as = "serde_with::DefaultOnError")]
     pub ntnurestrict: i32,
+    #[serde(alias = "courseTimes")]
     pub course_times: String,
+    #[serde(alias = "practicalTimes")]
     pub practical_times: String,
-    #[serde(default)]
+    #[serde(default, alias = "classRoomNo")]
     pub class_room_no: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "threeNode")]
     pub three_node: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "node")]
     pub node: Option<String>,
+    #[serde(alias = "contents")]
     pub contents: String,
-    #[serde(rename(serialize = "ntuPeople", deserialize = "NTU_People"))]
+    #[serde(
+        rename(serialize = "ntuPeople", deserialize = "NTU_People"),
+        alias = "ntuPeople"
+    )]
     pub ntu_people: i32,
-    #[serde(rename(serialize = "ntnuPeople", deserialize = "NTNU_People"))]
+    #[serde(
+        rename(serialize = "ntnuPeople", deserialize = "NTNU_People"),
+        alias = "ntnuPeople"
+    )]
     pub ntnu_people: i32,
+    #[serde(alias = "abroadPeople")]
     pub abroad_people: i32,
 }
 
+impl CourseInfo {
+    pub fn limit(&self) -> i32 {
+        seat_limit(self.restrict1, self.restrict2)
+    }
+
+    pub fn available_seats(&self) -> i32 {
+        available_seats(self.choose_student, self.restrict1, self.restrict2)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.available_seats() == 0
+    }
+
+    /// Parse `node` into time slots, in chronological order.
+    pub fn time_slots(&self) -> Result<Vec<TimeSlot>, QueryError> {
+        time_slots(self.node.as_deref())
+    }
+}
+
+/// A course from the `coursedetials` endpoint, in the same formats as [`CourseInfo`].
 #[serde_as]
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "PascalCase"))]
 pub struct CourseDetails {
+    #[serde(alias = "semester")]
     pub semester: String,
+    #[serde(alias = "courseNo")]
     pub course_no: String,
+    #[serde(alias = "courseName")]
     pub course_name: String,
+    #[serde(alias = "courseTeacher")]
     pub course_teacher: String,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "creditPoint")]
     pub credit_point: f32,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "courseTimes")]
     pub course_times: i32,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "practicalTimes")]
     pub practical_times: i32,
+    #[serde(alias = "requireOption")]
     pub require_option: String,
+    #[serde(alias = "allYear")]
     pub all_year: String,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "chooseStudent")]
     pub choose_student: i32,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "threeStudent")]
     pub three_student: i32,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "allStudent")]
     pub all_student: i32,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "restrict1")]
     pub restrict1: i32,
-    #[serde_as(deserialize_as = "serde_with::DisplayFromStr")]
+    #[serde_as(deserialize_as = "PickFirst<(DisplayFromStr, _)>")]
+    #[serde(alias = "restrict2")]
     pub restrict2: i32,
-    #[serde(rename(serialize = "ntuRestrict", deserialize = "NTURestrict"))]
+    #[serde(
+        rename(serialize = "ntuRestrict", deserialize = "NTURestrict"),
+        alias = "ntuRestrict"
+    )]
     #[serde_as(deserialize_as = "serde_with::DefaultOnError")]
     pub nturestrict: i32,
-    #[serde(rename(serialize = "ntnuRestrict", deserialize = "NTNURestrict"))]
+    #[serde(
+        rename(serialize = "ntnuRestrict", deserialize = "NTNURestrict"),
+        alias = "ntnuRestrict"
+    )]
     #[serde_as(deserialize_as = "serde_with::DefaultOnError")]
     pub ntnurestrict: Option<i32>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "classRoomNo")]
     pub class_room_no: Option<String>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "coreAbility")]
     pub core_ability: Option<String>,
-    #[serde(rename(deserialize = "CourseURL"))]
+    #[serde(rename(deserialize = "CourseURL"), alias = "courseUrl")]
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
     pub course_url: Option<String>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "courseObject")]
     pub course_object: Option<String>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "courseContent")]
     pub course_content: Option<String>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "courseTextbook")]
     pub course_textbook: Option<String>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "courseRefbook")]
     pub course_refbook: Option<String>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "courseNote")]
     pub course_note: Option<String>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "courseGrading")]
     pub course_grading: Option<String>,
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
+    #[serde(alias = "courseRemark")]
     pub course_remark: Option<String>,
-    #[serde(rename(deserialize = "Instruction_1"))]
-    #[serde_as(deserialize_as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(rename(deserialize = "Instruction_1"), alias = "instruction1")]
+    #[serde_as(deserialize_as = "Option<PickFirst<(DisplayFromStr, _)>>")]
     pub instruction_1: Option<i32>,
-    #[serde(rename(deserialize = "Instruction_2"))]
-    #[serde_as(deserialize_as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(rename(deserialize = "Instruction_2"), alias = "instruction2")]
+    #[serde_as(deserialize_as = "Option<PickFirst<(DisplayFromStr, _)>>")]
     pub instruction_2: Option<i32>,
-    #[serde(rename(deserialize = "Instruction_3"))]
-    #[serde_as(deserialize_as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(rename(deserialize = "Instruction_3"), alias = "instruction3")]
+    #[serde_as(deserialize_as = "Option<PickFirst<(DisplayFromStr, _)>>")]
     pub instruction_3: Option<i32>,
-    #[serde(rename(deserialize = "Instruction_4"))]
-    #[serde_as(deserialize_as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(rename(deserialize = "Instruction_4"), alias = "instruction4")]
+    #[serde_as(deserialize_as = "Option<PickFirst<(DisplayFromStr, _)>>")]
     pub instruction_4: Option<i32>,
-    #[serde(rename(deserialize = "Instruction_other"))]
+    #[serde(rename(deserialize = "Instruction_other"), alias = "instructionOther")]
     #[serde_as(deserialize_as = "serde_with::NoneAsEmptyString")]
     pub instruction_other: Option<String>,
 }
 
+impl CourseDetails {
+    pub fn limit(&self) -> i32 {
+        seat_limit(self.restrict1, self.restrict2)
+    }
+
+    pub fn available_seats(&self) -> i32 {
+        available_seats(self.choose_student, self.restrict1, self.restrict2)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.available_seats() == 0
+    }
+}
+
+/// Body of a `courses` request, in the same formats as [`CourseInfo`].
 #[serde_as]
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "PascalCase"))]
 pub struct SearchOptions {
+    #[serde(alias = "semester")]
     pub semester: String,
+    #[serde(alias = "courseNo")]
     pub course_no: String,
+    #[serde(alias = "courseName")]
     pub course_name: String,
+    #[serde(alias = "courseTeacher")]
     pub course_teacher: String,
+    #[serde(alias = "dimension")]
     pub dimension: String,
+    #[serde(alias = "courseNotes")]
     pub course_notes: String,
     #[serde_as(as = "BoolFromInt")]
+    #[serde(alias = "foreignLanguage")]
     pub foreign_language: bool,
     #[serde_as(as = "BoolFromInt")]
+    #[serde(alias = "onlyGeneral")]
     pub only_general: bool,
     #[serde_as(as = "BoolFromInt")]
-    #[serde(rename(serialize = "OnlyNTUST", deserialize = "OnleyNTUST"))]
+    #[serde(
+        rename(serialize = "OnlyNTUST", deserialize = "OnleyNTUST"),
+        alias = "OnlyNTUST"
+    )]
     // looks like a low-level naming mistake in the API
     pub only_ntust: bool,
     #[serde_as(as = "BoolFromInt")]
+    #[serde(alias = "onlyMaster")]
     pub only_master: bool,
     #[serde_as(as = "BoolFromInt")]
+    #[serde(alias = "onlyUnderGraduate")]
     pub only_under_graduate: bool,
     #[serde_as(as = "BoolFromInt")]
+    #[serde(alias = "onlyNode")]
     pub only_node: bool,
+    #[serde(alias = "language")]
     pub language: Language,
 }
 
 impl SearchOptions {
-    pub fn new(semester: &str, language: Language) -> Self {
+    /// `semester` accepts either a raw string such as `"1131"` or a [`Semester`],
+    /// it is validated when the search is sent.
+    pub fn new<S: ToString>(semester: S, language: Language) -> Self {
         Self {
             semester: semester.to_string(),
             course_no: String::new(),
@@ -216,13 +360,26 @@ pub fn merge_courses(courses: Vec<CourseInfo>) -> Vec<CourseInfo> {
             .or_insert(course);
     }
 
-    // ensure nodes are unique and in order
+    // ensure nodes are unique and in chronological order,
+    // unrecognized nodes are kept at the end in lexical order
     for course in course_map.values_mut() {
         if let Some(nodes) = &mut course.node {
-            let mut nodes_vec: Vec<&str> = nodes.split(',').collect();
-            nodes_vec.sort();
-            nodes_vec.dedup();
-            *nodes = nodes_vec.join(",");
+            let mut nodes_vec: Vec<(Option<TimeSlot>, &str)> = nodes
+                .split(',')
+                .map(|n| (n.parse::<TimeSlot>().ok(), n))
+                .collect();
+            nodes_vec.sort_by(|(a_slot, a), (b_slot, b)| match (a_slot, b_slot) {
+                (Some(a_slot), Some(b_slot)) => a_slot.cmp(b_slot),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => a.cmp(b),
+            });
+            nodes_vec.dedup_by(|(_, a), (_, b)| a == b);
+            *nodes = nodes_vec
+                .into_iter()
+                .map(|(_, n)| n)
+                .collect::<Vec<&str>>()
+                .join(",");
         }
     }
 
@@ -235,25 +392,204 @@ pub fn default_reqwest_builder() -> reqwest::ClientBuilder {
         .timeout(DEFAULT_TIMEOUT)
 }
 
+/// Maximum length of a response body kept in a `QueryError`.
+pub const ERROR_BODY_LIMIT: usize = 1024;
+
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum QueryError {
+    /// The input was rejected before any request was sent.
     InputError(String),
-    HttpError(String),
-    ParseError(String),
+    /// The request timed out.
+    TimeoutError(Arc<reqwest::Error>),
+    /// Could not connect to the server.
+    ConnectionError(Arc<reqwest::Error>),
+    /// Any other error raised while sending the request or reading the response.
+    HttpError(Arc<reqwest::Error>),
+    /// The server responded with a non-2xx status, `body` is truncated to `ERROR_BODY_LIMIT`.
+    StatusError { status: StatusCode, body: String },
+    /// The server returned no course for the query.
+    NotFoundError,
+    /// The response body could not be deserialized.
+    ///
+    /// `path` points at the offending field, e.g. `[0].CreditPoint`,
+    /// `body` is truncated to `ERROR_BODY_LIMIT`.
+    ParseError {
+        source: Arc<serde_json::Error>,
+        path: String,
+        body: String,
+    },
+    /// A cassette in replay mode has no recording of the request.
+    ReplayError(String),
+    /// Reading from or writing to a local file failed.
+    IoError(Arc<std::io::Error>),
+    /// Reading from or writing to a `store::Store` failed.
+    #[cfg(feature = "store")]
+    StoreError(Arc<rusqlite::Error>),
+}
+
+impl QueryError {
+    pub fn is_input(&self) -> bool {
+        matches!(self, QueryError::InputError(_))
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, QueryError::TimeoutError(_))
+    }
+
+    pub fn is_connection(&self) -> bool {
+        matches!(self, QueryError::ConnectionError(_))
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, QueryError::NotFoundError)
+    }
+
+    pub fn is_parse(&self) -> bool {
+        matches!(self, QueryError::ParseError { .. })
+    }
+
+    /// HTTP status of a `StatusError`, or of the response a client error was raised for.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            QueryError::StatusError { status, .. } => Some(*status),
+            QueryError::TimeoutError(e)
+            | QueryError::ConnectionError(e)
+            | QueryError::HttpError(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn status_error(status: StatusCode, body: &[u8]) -> Self {
+        QueryError::StatusError {
+            status,
+            body: truncate_body(body),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QueryError::TimeoutError(e)
+            | QueryError::ConnectionError(e)
+            | QueryError::HttpError(e) => Some(e.as_ref()),
+            QueryError::ParseError { source, .. } => Some(source.as_ref()),
+            QueryError::IoError(e) => Some(e.as_ref()),
+            #[cfg(feature = "store")]
+            QueryError::StoreError(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for QueryError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            QueryError::TimeoutError(Arc::new(e))
+        } else if e.is_connect() {
+            QueryError::ConnectionError(Arc::new(e))
+        } else {
+            QueryError::HttpError(Arc::new(e))
+        }
+    }
+}
+
+impl From<std::io::Error> for QueryError {
+    fn from(e: std::io::Error) -> Self {
+        QueryError::IoError(Arc::new(e))
+    }
 }
 
-impl std::error::Error for QueryError {}
+#[cfg(feature = "store")]
+impl From<rusqlite::Error> for QueryError {
+    fn from(e: rusqlite::Error) -> Self {
+        QueryError::StoreError(Arc::new(e))
+    }
+}
+
+impl From<std::convert::Infallible> for QueryError {
+    fn from(e: std::convert::Infallible) -> Self {
+        match e {}
+    }
+}
 
 impl fmt::Display for QueryError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             QueryError::InputError(msg) => write!(f, "Input Error: {}", msg),
-            QueryError::HttpError(msg) => write!(f, "HTTP Error: {}", msg),
-            QueryError::ParseError(msg) => write!(f, "Parse Error: {}", msg),
+            QueryError::TimeoutError(e) => write!(f, "Timeout Error: {}", e),
+            QueryError::ConnectionError(e) => write!(f, "Connection Error: {}", e),
+            QueryError::HttpError(e) => write!(f, "HTTP Error: {}", e),
+            QueryError::StatusError { status, .. } => write!(f, "Status Error: {}", status),
+            QueryError::NotFoundError => write!(f, "Not Found Error: no course found"),
+            QueryError::ParseError { source, path, .. } => {
+                write!(f, "Parse Error: {} at {}", source, path)
+            }
+            QueryError::ReplayError(msg) => write!(f, "Replay Error: {}", msg),
+            QueryError::IoError(e) => write!(f, "IO Error: {}", e),
+            #[cfg(feature = "store")]
+            QueryError::StoreError(e) => write!(f, "Store Error: {}", e),
         }
     }
 }
 
+/// Deserialize a response body, reporting the offending field on failure.
+pub(crate) fn from_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, QueryError> {
+    let de = &mut serde_json::Deserializer::from_slice(body);
+
+    serde_path_to_error::deserialize(de).map_err(|e| QueryError::ParseError {
+        path: e.path().to_string(),
+        source: Arc::new(e.into_inner()),
+        body: truncate_body(body),
+    })
+}
+
+fn truncate_body(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    match text.char_indices().nth(ERROR_BODY_LIMIT) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.into_owned(),
+    }
+}
+
+/// Enrollment limit, the lower of `restrict1` and `restrict2`.
+///
+/// The `limit`, `available_seats` and `is_full` methods of every type carrying
+/// enrollment counts are built on this and `available_seats`.
+pub(crate) fn seat_limit(restrict1: i32, restrict2: i32) -> i32 {
+    restrict1.min(restrict2)
+}
+
+/// Seats left under the limit, never negative.
+pub(crate) fn available_seats(choose_student: i32, restrict1: i32, restrict2: i32) -> i32 {
+    (seat_limit(restrict1, restrict2) - choose_student).max(0)
+}
+
+/// Parse a `node` list into time slots, in chronological order and without duplicates.
+pub(crate) fn time_slots(node: Option<&str>) -> Result<Vec<TimeSlot>, QueryError> {
+    let mut slots = match node {
+        Some(node) => TimeSlot::parse_list(node)?,
+        None => Vec::new(),
+    };
+    slots.sort();
+    slots.dedup();
+    Ok(slots)
+}
+
+/// Whether `c` is a CJK character, which the text search helpers treat as a word of its own.
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // Hiragana, Katakana
+        | 0x3100..=0x312F // Bopomofo
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7A3 // Hangul
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // CJK Extension B and later
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,7 +643,7 @@ mod tests {
                 practical_times: String::from("0"),
                 class_room_no: None,
                 three_node: None,
-                node: Some(String::from("T1,T2")),
+                node: Some(String::from("M10,T2,M2")),
                 contents: String::from("學號雙數／EMI課程／英語授課"),
                 ntu_people: 0,
                 ntnu_people: 0,
@@ -317,6 +653,67 @@ mod tests {
 
         let merged_courses = crate::merge_courses(courses);
 
-        println!("{:#?}", merged_courses);
+        assert_eq!(merged_courses.len(), 1);
+        assert_eq!(merged_courses[0].node.as_deref(), Some("M2,M10,T2,R1"));
+        assert_eq!(
+            merged_courses[0].time_slots().unwrap(),
+            TimeSlot::parse_list("M2,M10,T2,R1").unwrap()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let wire: std::collections::HashMap<Language, Vec<CourseDetails>> =
+            serde_json::from_str(crate::testing::DETAILS_FIXTURE).unwrap();
+        let details = &wire[&Language::Zh][0];
+        let json = serde_json::to_string(details).unwrap();
+        assert!(json.contains(r#""creditPoint":3.0"#));
+        assert_eq!(
+            &serde_json::from_str::<CourseDetails>(&json).unwrap(),
+            details
+        );
+
+        let wire: std::collections::HashMap<Language, Vec<CourseInfo>> =
+            serde_json::from_str(crate::testing::COURSES_FIXTURE).unwrap();
+        let info = &wire[&Language::En][0];
+        let json = serde_json::to_string(info).unwrap();
+        assert!(json.contains(r#""ntuRestrict":9999"#));
+        assert_eq!(&serde_json::from_str::<CourseInfo>(&json).unwrap(), info);
+
+        let mut options = SearchOptions::new("1131", Language::En);
+        options.only_ntust = true;
+        let json = serde_json::to_value(&options).unwrap();
+        assert_eq!(json["OnlyNTUST"], 1);
+        let reloaded: SearchOptions = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&reloaded).unwrap(), json);
+    }
+
+    #[test]
+    fn parse_error() {
+        let body = br#"[{"Semester": "1131", "CreditPoint": "three"}]"#;
+
+        let e = from_json::<Vec<CourseInfo>>(body).unwrap_err();
+
+        match &e {
+            QueryError::ParseError { path, body, .. } => {
+                assert_eq!(path, "[0].CreditPoint");
+                assert!(body.contains("three"));
+            }
+            _ => panic!("unexpected error: {:?}", e),
+        }
+        assert!(e.is_parse());
+        assert!(std::error::Error::source(&e).is_some());
+    }
+
+    #[test]
+    fn status_error() {
+        let body = "x".repeat(ERROR_BODY_LIMIT * 2);
+        let e = QueryError::status_error(StatusCode::BAD_GATEWAY, body.as_bytes());
+
+        assert_eq!(e.status(), Some(StatusCode::BAD_GATEWAY));
+        match e {
+            QueryError::StatusError { body, .. } => assert_eq!(body.len(), ERROR_BODY_LIMIT),
+            _ => unreachable!(),
+        }
     }
 }
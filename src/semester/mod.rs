@@ -0,0 +1,226 @@
+use std::{fmt, str::FromStr};
+
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+
+use crate::QueryError;
+
+/// Offset between the ROC (Minguo) calendar and the Gregorian calendar.
+pub const ROC_YEAR_OFFSET: u16 = 1911;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Term {
+    Fall = 1,
+    Spring = 2,
+    Summer = 3,
+}
+
+impl Term {
+    pub fn from_digit(digit: u8) -> Option<Self> {
+        match digit {
+            1 => Some(Term::Fall),
+            2 => Some(Term::Spring),
+            3 => Some(Term::Summer),
+            _ => None,
+        }
+    }
+
+    pub fn as_digit(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// A semester as used by the API, e.g. `"1131"`: ROC academic year `113`, term `1`.
+///
+/// Serialized as its string form.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct Semester {
+    pub academic_year: u16,
+    pub term: Term,
+}
+
+impl Semester {
+    pub fn new(academic_year: u16, term: Term) -> Self {
+        Self {
+            academic_year,
+            term,
+        }
+    }
+
+    /// The next semester, `None` after academic year `u16::MAX`.
+    pub fn next(&self) -> Option<Self> {
+        match self.term {
+            Term::Fall => Some(Self::new(self.academic_year, Term::Spring)),
+            Term::Spring => Some(Self::new(self.academic_year, Term::Summer)),
+            Term::Summer => self
+                .academic_year
+                .checked_add(1)
+                .map(|year| Self::new(year, Term::Fall)),
+        }
+    }
+
+    /// The previous semester, `None` before academic year 1.
+    pub fn prev(&self) -> Option<Self> {
+        match self.term {
+            Term::Fall => self
+                .academic_year
+                .checked_sub(1)
+                .filter(|year| *year > 0)
+                .map(|year| Self::new(year, Term::Summer)),
+            Term::Spring => Some(Self::new(self.academic_year, Term::Fall)),
+            Term::Summer => Some(Self::new(self.academic_year, Term::Spring)),
+        }
+    }
+
+    /// Gregorian year in which the academic year begins, e.g. `2024` for `1131`,
+    /// `None` if it does not fit in a `u16`.
+    pub fn gregorian_year(&self) -> Option<u16> {
+        self.academic_year.checked_add(ROC_YEAR_OFFSET)
+    }
+
+    /// Gregorian year in which this term's classes are held, `None` if it does not fit in a `u16`.
+    pub fn gregorian_term_year(&self) -> Option<u16> {
+        match self.term {
+            Term::Fall => self.gregorian_year(),
+            Term::Spring | Term::Summer => self.gregorian_year()?.checked_add(1),
+        }
+    }
+}
+
+impl FromStr for Semester {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<Semester, Self::Err> {
+        let invalid = || QueryError::InputError(format!("invalid semester: {:?}", input));
+
+        if !(3..=4).contains(&input.len()) || !input.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let (year, term) = input.split_at(input.len() - 1);
+        // leading zeros would not survive a round trip through `Display`
+        if year.starts_with('0') {
+            return Err(invalid());
+        }
+        let academic_year = year.parse::<u16>().map_err(|_| invalid())?;
+        let term = term
+            .parse::<u8>()
+            .ok()
+            .and_then(Term::from_digit)
+            .ok_or_else(invalid)?;
+
+        if academic_year == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Semester::new(academic_year, term))
+    }
+}
+
+impl fmt::Display for Semester {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.academic_year, self.term.as_digit())
+    }
+}
+
+impl TryFrom<&str> for Semester {
+    type Error = QueryError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<&String> for Semester {
+    type Error = QueryError;
+
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for Semester {
+    type Error = QueryError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<&Semester> for Semester {
+    fn from(value: &Semester) -> Self {
+        *value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "1131".parse::<Semester>().unwrap(),
+            Semester::new(113, Term::Fall)
+        );
+        assert_eq!(
+            "992".parse::<Semester>().unwrap(),
+            Semester::new(99, Term::Spring)
+        );
+        assert_eq!("1123".parse::<Semester>().unwrap().term, Term::Summer);
+
+        for input in [
+            "", "11", "1134", "1130", "11a1", "01", "0001", "0991", "011", "11311", " 1131",
+        ] {
+            assert!(input.parse::<Semester>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let semester: Semester = "1131".parse().unwrap();
+
+        let next = semester.next().unwrap();
+        assert_eq!(next.to_string(), "1132");
+        assert_eq!(next.next().unwrap().to_string(), "1133");
+        assert_eq!(next.next().unwrap().next().unwrap().to_string(), "1141");
+        assert_eq!(semester.prev().unwrap().to_string(), "1123");
+        assert_eq!(next.prev(), Some(semester));
+        assert_eq!(Semester::new(1, Term::Fall).prev(), None);
+        assert_eq!(Semester::new(0, Term::Fall).prev(), None);
+        assert_eq!(
+            Semester::new(u16::MAX, Term::Spring).next(),
+            Some(Semester::new(u16::MAX, Term::Summer))
+        );
+        assert_eq!(Semester::new(u16::MAX, Term::Summer).next(), None);
+    }
+
+    #[test]
+    fn gregorian() {
+        let semester: Semester = "1131".parse().unwrap();
+        assert_eq!(semester.gregorian_year(), Some(2024));
+        assert_eq!(semester.gregorian_term_year(), Some(2024));
+        assert_eq!(semester.next().unwrap().gregorian_term_year(), Some(2025));
+
+        let last = u16::MAX - ROC_YEAR_OFFSET;
+        assert_eq!(
+            Semester::new(last, Term::Fall).gregorian_term_year(),
+            Some(u16::MAX)
+        );
+        assert_eq!(
+            Semester::new(last, Term::Spring).gregorian_term_year(),
+            None
+        );
+        assert_eq!(Semester::new(last + 1, Term::Fall).gregorian_year(), None);
+    }
+
+    #[test]
+    fn serde() {
+        let semester: Semester = "1122".parse().unwrap();
+        let json = serde_json::to_string(&semester).unwrap();
+        assert_eq!(json, "\"1122\"");
+        assert_eq!(serde_json::from_str::<Semester>(&json).unwrap(), semester);
+        assert!(serde_json::from_str::<Semester>("\"abc\"").is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    CourseDetails, CourseInfo, CourseNo, Language, QueryError, SearchOptions, Semester, Q,
+};
+
+/// Anything that can answer course searches and queries.
+///
+/// Write applications against this trait to swap the live API for offline data,
+/// e.g. a [`MemorySource`] in tests and demos.
+pub trait CourseSource: Send + Sync {
+    fn search(
+        &self,
+        options: &SearchOptions,
+        merge_courses: bool,
+    ) -> impl Future<Output = Result<Vec<CourseInfo>, QueryError>> + Send;
+
+    fn query(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> impl Future<Output = Result<CourseDetails, QueryError>> + Send;
+}
+
+impl CourseSource for Q {
+    async fn search(
+        &self,
+        options: &SearchOptions,
+        merge_courses: bool,
+    ) -> Result<Vec<CourseInfo>, QueryError> {
+        Q::search(self, options, merge_courses).await
+    }
+
+    async fn query(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> Result<CourseDetails, QueryError> {
+        Q::query(self, semester, course_no, language).await
+    }
+}
+
+/// Courses held in memory.
+///
+/// `search` matches the semester, a `course_no` prefix and substrings of
+/// `course_name` and `course_teacher`, the remaining options are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    courses: HashMap<Language, Vec<CourseInfo>>,
+    details: HashMap<Language, Vec<CourseDetails>>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load fixtures in the API's own format, an object of `zh` and `en` arrays
+    /// as used by `testing::MockServer`.
+    pub fn from_fixtures(courses: &str, details: &str) -> Result<Self, QueryError> {
+        let mut source = Self::new();
+        for (language, courses) in
+            crate::from_json::<HashMap<Language, Vec<CourseInfo>>>(courses.as_bytes())?
+        {
+            source.add_courses(language, courses);
+        }
+        for (language, details) in
+            crate::from_json::<HashMap<Language, Vec<CourseDetails>>>(details.as_bytes())?
+        {
+            source.add_details(language, details);
+        }
+        Ok(source)
+    }
+
+    pub fn add_courses<I>(&mut self, language: Language, courses: I)
+    where
+        I: IntoIterator<Item = CourseInfo>,
+    {
+        self.courses.entry(language).or_default().extend(courses);
+    }
+
+    /// Add details, replacing any with the same semester and course number.
+    pub fn add_details<I>(&mut self, language: Language, details: I)
+    where
+        I: IntoIterator<Item = CourseDetails>,
+    {
+        let entries = self.details.entry(language).or_default();
+        for details in details {
+            entries.retain(|d| d.semester != details.semester || d.course_no != details.course_no);
+            entries.push(details);
+        }
+    }
+
+    fn search_sync(
+        &self,
+        options: &SearchOptions,
+        merge_courses: bool,
+    ) -> Result<Vec<CourseInfo>, QueryError> {
+        let courses = self.courses.get(&options.language).into_iter().flatten();
+        filter_courses(options, courses.cloned(), merge_courses)
+    }
+
+    fn query_sync(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> Result<CourseDetails, QueryError> {
+        let semester = semester.to_string();
+
+        self.details
+            .get(&language)
+            .into_iter()
+            .flatten()
+            .find(|d| d.semester == semester && d.course_no == course_no.as_str())
+            .cloned()
+            .ok_or(QueryError::NotFoundError)
+    }
+}
+
+/// Apply the options an offline source understands, see [`MemorySource`].
+pub(crate) fn filter_courses<I>(
+    options: &SearchOptions,
+    courses: I,
+    merge_courses: bool,
+) -> Result<Vec<CourseInfo>, QueryError>
+where
+    I: IntoIterator<Item = CourseInfo>,
+{
+    let semester = options.semester.parse::<Semester>()?.to_string();
+    let course_no = options.course_no.to_ascii_uppercase();
+
+    let courses: Vec<CourseInfo> = courses
+        .into_iter()
+        .filter(|c| c.semester == semester)
+        .filter(|c| c.course_no.starts_with(&course_no))
+        .filter(|c| c.course_name.contains(&options.course_name))
+        .filter(|c| c.course_teacher.contains(&options.course_teacher))
+        .collect();
+
+    Ok(if merge_courses {
+        crate::merge_courses(courses)
+    } else {
+        courses
+    })
+}
+
+impl CourseSource for MemorySource {
+    async fn search(
+        &self,
+        options: &SearchOptions,
+        merge_courses: bool,
+    ) -> Result<Vec<CourseInfo>, QueryError> {
+        self.search_sync(options, merge_courses)
+    }
+
+    async fn query(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> Result<CourseDetails, QueryError> {
+        self.query_sync(semester, course_no, language)
+    }
+}
+
+/// Wraps another source and keeps successful results in memory for `ttl`.
+///
+/// Unlike `ClientBuilder::cache`, which stores raw response bodies, this works
+/// with any source and stores the parsed values.
+#[derive(Debug)]
+pub struct CachedSource<S> {
+    inner: S,
+    ttl: Duration,
+    searches: Mutex<Entries<Vec<CourseInfo>>>,
+    queries: Mutex<Entries<CourseDetails>>,
+}
+
+/// Cached values by key, with their expiry, `None` for never.
+type Entries<T> = HashMap<String, (Option<Instant>, T)>;
+
+impl<S: CourseSource> CachedSource<S> {
+    pub fn new(inner: S, ttl: Duration) -> Self {
+        CachedSource {
+            inner,
+            ttl,
+            searches: Mutex::new(HashMap::new()),
+            queries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Drop every cached result.
+    pub fn clear(&self) {
+        self.searches.lock().unwrap().clear();
+        self.queries.lock().unwrap().clear();
+    }
+}
+
+fn lookup<T: Clone>(entries: &Mutex<Entries<T>>, key: &str) -> Option<T> {
+    let mut entries = entries.lock().unwrap();
+    match entries.get(key) {
+        Some((expires_at, value)) if expires_at.is_none_or(|t| t > Instant::now()) => {
+            Some(value.clone())
+        }
+        Some(_) => {
+            entries.remove(key);
+            None
+        }
+        None => None,
+    }
+}
+
+impl<S: CourseSource> CourseSource for CachedSource<S> {
+    async fn search(
+        &self,
+        options: &SearchOptions,
+        merge_courses: bool,
+    ) -> Result<Vec<CourseInfo>, QueryError> {
+        // serializing plain strings and bools cannot fail
+        let key = format!(
+            "{} {}",
+            serde_json::to_string(options).unwrap(),
+            merge_courses
+        );
+
+        if let Some(courses) = lookup(&self.searches, &key) {
+            return Ok(courses);
+        }
+
+        let courses = self.inner.search(options, merge_courses).await?;
+        self.searches
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now().checked_add(self.ttl), courses.clone()));
+        Ok(courses)
+    }
+
+    async fn query(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> Result<CourseDetails, QueryError> {
+        let key = format!("{} {} {}", semester, course_no, language.as_str());
+
+        if let Some(details) = lookup(&self.queries, &key) {
+            return Ok(details);
+        }
+
+        let details = self.inner.query(semester, course_no, language).await?;
+        self.queries
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now().checked_add(self.ttl), details.clone()));
+        Ok(details)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{MockServer, COURSES_FIXTURE, DETAILS_FIXTURE},
+        ClientBuilder,
+    };
+
+    // written against the trait, as an application would be
+    async fn seats<S: CourseSource>(source: &S, course_no: &str) -> Result<i32, QueryError> {
+        let semester: Semester = "1131".parse()?;
+        let course_no: CourseNo = course_no.parse()?;
+        let details = source.query(semester, &course_no, Language::Zh).await?;
+        Ok(details.available_seats())
+    }
+
+    #[tokio::test]
+    async fn memory_matches_server() {
+        let memory = MemorySource::from_fixtures(COURSES_FIXTURE, DETAILS_FIXTURE).unwrap();
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        assert_eq!(seats(&memory, "CS1003302").await.unwrap(), 3);
+        assert_eq!(seats(&client, "CS1003302").await.unwrap(), 3);
+        assert!(seats(&memory, "EE1003302")
+            .await
+            .unwrap_err()
+            .is_not_found());
+
+        let mut options = SearchOptions::new("1131", Language::En);
+        options.course_no = String::from("cs");
+        // merging does not keep the order
+        let mut from_memory = CourseSource::search(&memory, &options, true).await.unwrap();
+        let mut from_client = CourseSource::search(&client, &options, true).await.unwrap();
+        from_memory.sort_by(|a, b| a.course_no.cmp(&b.course_no));
+        from_client.sort_by(|a, b| a.course_no.cmp(&b.course_no));
+        assert_eq!(from_memory.len(), 2);
+        assert_eq!(from_memory, from_client);
+    }
+
+    #[tokio::test]
+    async fn cached() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+        let cached = CachedSource::new(client, Duration::from_secs(60));
+
+        assert_eq!(seats(&cached, "CS1003302").await.unwrap(), 3);
+        assert_eq!(seats(&cached, "CS1003302").await.unwrap(), 3);
+        assert_eq!(server.hits(), 1);
+
+        // errors are not cached
+        assert!(seats(&cached, "EE1003302").await.is_err());
+        assert!(seats(&cached, "EE1003302").await.is_err());
+        assert_eq!(server.hits(), 3);
+
+        cached.clear();
+        assert_eq!(seats(&cached, "CS1003302").await.unwrap(), 3);
+        assert_eq!(server.hits(), 4);
+
+        // a TTL past the end of `Instant` never expires
+        let client = ClientBuilder::new().api_url(server.url()).build();
+        let forever = CachedSource::new(client, Duration::MAX);
+        assert_eq!(seats(&forever, "CS1003302").await.unwrap(), 3);
+        assert_eq!(seats(&forever, "CS1003302").await.unwrap(), 3);
+        assert_eq!(server.hits(), 5);
+    }
+}
@@ -0,0 +1,557 @@
+//! Archive of search results and course details in SQLite.
+//!
+//! Every row is keyed by semester, course number, language and fetch time, so
+//! repeated snapshots of the same catalog are kept side by side for historical analysis.
+//! The full record is stored in the canonical JSON format next to a few columns
+//! that are handy in plain SQL.
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub use rusqlite::Error;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    enrollment::{Sample, SampleStore},
+    merge_courses,
+    source::filter_courses,
+    CourseDetails, CourseInfo, CourseNo, CourseSource, Language, QueryError, SearchOptions,
+    Semester,
+};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS course_info (
+    semester        TEXT    NOT NULL,
+    course_no       TEXT    NOT NULL,
+    language        TEXT    NOT NULL,
+    fetched_at      INTEGER NOT NULL,
+    course_name     TEXT    NOT NULL,
+    course_teacher  TEXT    NOT NULL,
+    credit_point    REAL    NOT NULL,
+    choose_student  INTEGER NOT NULL,
+    restrict1       INTEGER NOT NULL,
+    restrict2       INTEGER NOT NULL,
+    node            TEXT,
+    data            TEXT    NOT NULL,
+    PRIMARY KEY (semester, course_no, language, fetched_at)
+);
+
+CREATE TABLE IF NOT EXISTS course_details (
+    semester        TEXT    NOT NULL,
+    course_no       TEXT    NOT NULL,
+    language        TEXT    NOT NULL,
+    fetched_at      INTEGER NOT NULL,
+    course_name     TEXT    NOT NULL,
+    course_teacher  TEXT    NOT NULL,
+    credit_point    REAL    NOT NULL,
+    choose_student  INTEGER NOT NULL,
+    restrict1       INTEGER NOT NULL,
+    restrict2       INTEGER NOT NULL,
+    data            TEXT    NOT NULL,
+    PRIMARY KEY (semester, course_no, language, fetched_at)
+);
+
+CREATE TABLE IF NOT EXISTS enrollment (
+    semester        TEXT    NOT NULL,
+    course_no       TEXT    NOT NULL,
+    sampled_at      INTEGER NOT NULL,
+    choose_student  INTEGER NOT NULL,
+    all_student     INTEGER NOT NULL,
+    three_student   INTEGER NOT NULL,
+    ntu_people      INTEGER,
+    ntnu_people     INTEGER,
+    abroad_people   INTEGER,
+    restrict1       INTEGER NOT NULL,
+    restrict2       INTEGER NOT NULL,
+    PRIMARY KEY (semester, course_no, sampled_at)
+);
+
+CREATE INDEX IF NOT EXISTS course_info_snapshot ON course_info (semester, language, fetched_at);
+";
+
+/// A SQLite database of course snapshots.
+///
+/// Fetch times are stored as Unix milliseconds in `fetched_at`.
+#[derive(Debug)]
+pub struct Store {
+    conn: Mutex<Connection>,
+}
+
+impl Store {
+    /// Open or create a database file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Save a search result taken at `fetched_at`, returns the number of courses stored.
+    ///
+    /// Courses appearing more than once, one entry per time slot group, are merged
+    /// as `merge_courses` does, so every time slot is kept.
+    pub fn insert_courses(
+        &self,
+        language: Language,
+        courses: &[CourseInfo],
+        fetched_at: SystemTime,
+    ) -> Result<usize> {
+        let mut semesters: BTreeMap<&str, Vec<CourseInfo>> = BTreeMap::new();
+        for c in courses {
+            semesters.entry(&c.semester).or_default().push(c.clone());
+        }
+        let courses: Vec<CourseInfo> = semesters.into_values().flat_map(merge_courses).collect();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO course_info (semester, course_no, language, fetched_at,
+                    course_name, course_teacher, credit_point, choose_student, restrict1, restrict2,
+                    node, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            )?;
+            for c in &courses {
+                stmt.execute(params![
+                    c.semester,
+                    c.course_no,
+                    language.as_str(),
+                    to_millis(fetched_at),
+                    c.course_name,
+                    c.course_teacher,
+                    c.credit_point,
+                    c.choose_student,
+                    c.restrict1,
+                    c.restrict2,
+                    c.node,
+                    to_json(c)?,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(courses.len())
+    }
+
+    /// Save course details taken at `fetched_at`, returns the number of courses stored.
+    ///
+    /// Details of the same course appearing more than once keep their last occurrence.
+    pub fn insert_details(
+        &self,
+        language: Language,
+        details: &[CourseDetails],
+        fetched_at: SystemTime,
+    ) -> Result<usize> {
+        let details: BTreeMap<(&str, &str), &CourseDetails> = details
+            .iter()
+            .map(|d| ((d.semester.as_str(), d.course_no.as_str()), d))
+            .collect();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO course_details (semester, course_no, language, fetched_at,
+                    course_name, course_teacher, credit_point, choose_student, restrict1, restrict2,
+                    data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            for d in details.values() {
+                stmt.execute(params![
+                    d.semester,
+                    d.course_no,
+                    language.as_str(),
+                    to_millis(fetched_at),
+                    d.course_name,
+                    d.course_teacher,
+                    d.credit_point,
+                    d.choose_student,
+                    d.restrict1,
+                    d.restrict2,
+                    to_json(d)?,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(details.len())
+    }
+
+    /// Fetch times of the search snapshots of a semester, oldest first.
+    pub fn snapshots(&self, semester: Semester, language: Language) -> Result<Vec<SystemTime>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT fetched_at FROM course_info
+             WHERE semester = ?1 AND language = ?2 ORDER BY fetched_at",
+        )?;
+        let rows = stmt.query_map(params![semester.to_string(), language.as_str()], |row| {
+            row.get(0).map(from_millis)
+        })?;
+        rows.collect()
+    }
+
+    /// The courses of the snapshot taken at `fetched_at`, ordered by course number.
+    pub fn courses_at(
+        &self,
+        semester: Semester,
+        language: Language,
+        fetched_at: SystemTime,
+    ) -> Result<Vec<CourseInfo>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT data FROM course_info
+             WHERE semester = ?1 AND language = ?2 AND fetched_at = ?3 ORDER BY course_no",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                semester.to_string(),
+                language.as_str(),
+                to_millis(fetched_at)
+            ],
+            |row| row.get::<_, String>(0),
+        )?;
+        rows.map(|data| from_json(&data?)).collect()
+    }
+
+    /// The courses of the most recent snapshot, empty if there is none.
+    pub fn latest_courses(
+        &self,
+        semester: Semester,
+        language: Language,
+    ) -> Result<Vec<CourseInfo>> {
+        match self.snapshots(semester, language)?.last() {
+            Some(fetched_at) => self.courses_at(semester, language, *fetched_at),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Every recorded search hit of one course, oldest first.
+    pub fn course_history(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> Result<Vec<(SystemTime, CourseInfo)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT fetched_at, data FROM course_info
+             WHERE semester = ?1 AND course_no = ?2 AND language = ?3 ORDER BY fetched_at",
+        )?;
+        let rows = stmt.query_map(
+            params![semester.to_string(), course_no.as_str(), language.as_str()],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        )?;
+        rows.map(|row| {
+            let (fetched_at, data) = row?;
+            Ok((from_millis(fetched_at), from_json(&data)?))
+        })
+        .collect()
+    }
+
+    /// The most recently fetched details of a course.
+    pub fn latest_details(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> Result<Option<CourseDetails>> {
+        let conn = self.conn.lock().unwrap();
+        let data = conn
+            .query_row(
+                "SELECT data FROM course_details
+                 WHERE semester = ?1 AND course_no = ?2 AND language = ?3
+                 ORDER BY fetched_at DESC LIMIT 1",
+                params![semester.to_string(), course_no.as_str(), language.as_str()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        data.map(|data| from_json(&data)).transpose()
+    }
+
+    /// Run arbitrary SQL against the database, for analysis the helpers do not cover.
+    pub fn with_connection<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&Connection) -> T,
+    {
+        f(&self.conn.lock().unwrap())
+    }
+}
+
+/// Answers from the latest snapshot, see `MemorySource` for the supported search options.
+impl CourseSource for Store {
+    async fn search(
+        &self,
+        options: &SearchOptions,
+        merge_courses: bool,
+    ) -> std::result::Result<Vec<CourseInfo>, QueryError> {
+        let semester = options.semester.parse()?;
+        let courses = self.latest_courses(semester, options.language)?;
+        filter_courses(options, courses, merge_courses)
+    }
+
+    async fn query(
+        &self,
+        semester: Semester,
+        course_no: &CourseNo,
+        language: Language,
+    ) -> std::result::Result<CourseDetails, QueryError> {
+        self.latest_details(semester, course_no, language)?
+            .ok_or(QueryError::NotFoundError)
+    }
+}
+
+/// Samples go to the `enrollment` table.
+impl SampleStore for Store {
+    fn append(&self, samples: &[Sample]) -> std::result::Result<(), QueryError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO enrollment (semester, course_no, sampled_at,
+                    choose_student, all_student, three_student, ntu_people, ntnu_people,
+                    abroad_people, restrict1, restrict2)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            for s in samples {
+                stmt.execute(params![
+                    s.semester,
+                    s.course_no,
+                    to_millis(s.sampled_at),
+                    s.choose_student,
+                    s.all_student,
+                    s.three_student,
+                    s.ntu_people,
+                    s.ntnu_people,
+                    s.abroad_people,
+                    s.restrict1,
+                    s.restrict2,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Ordered by sample time.
+    fn samples(&self) -> std::result::Result<Vec<Sample>, QueryError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT semester, course_no, sampled_at, choose_student, all_student, three_student,
+                ntu_people, ntnu_people, abroad_people, restrict1, restrict2
+             FROM enrollment ORDER BY sampled_at, semester, course_no",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Sample {
+                semester: row.get(0)?,
+                course_no: row.get(1)?,
+                sampled_at: from_millis(row.get(2)?),
+                choose_student: row.get(3)?,
+                all_student: row.get(4)?,
+                three_student: row.get(5)?,
+                ntu_people: row.get(6)?,
+                ntnu_people: row.get(7)?,
+                abroad_people: row.get(8)?,
+                restrict1: row.get(9)?,
+                restrict2: row.get(10)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_>>()?)
+    }
+}
+
+fn to_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn from_millis(millis: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis.max(0) as u64)
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(value).map_err(|e| Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(data: &str) -> Result<T> {
+    serde_json::from_str(data)
+        .map_err(|e| Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::testing::{COURSES_FIXTURE, DETAILS_FIXTURE};
+
+    fn fixtures() -> (Vec<CourseInfo>, Vec<CourseDetails>) {
+        let mut courses: HashMap<Language, Vec<CourseInfo>> =
+            serde_json::from_str(COURSES_FIXTURE).unwrap();
+        let mut details: HashMap<Language, Vec<CourseDetails>> =
+            serde_json::from_str(DETAILS_FIXTURE).unwrap();
+
+        let in_1131 = |semester: &String| semester == "1131";
+        let mut courses = courses.remove(&Language::Zh).unwrap();
+        courses.retain(|c| in_1131(&c.semester));
+        let mut details = details.remove(&Language::Zh).unwrap();
+        details.retain(|d| in_1131(&d.semester));
+        (courses, details)
+    }
+
+    #[test]
+    fn snapshots() {
+        let store = Store::open_in_memory().unwrap();
+        let (mut courses, details) = fixtures();
+        let semester: Semester = "1131".parse().unwrap();
+        let course_no: CourseNo = "CS1003302".parse().unwrap();
+
+        let first = UNIX_EPOCH + Duration::from_secs(1_726_000_000);
+        let second = first + Duration::from_secs(3600);
+
+        assert_eq!(
+            store.insert_courses(Language::Zh, &courses, first).unwrap(),
+            3
+        );
+        courses[0].choose_student += 1;
+        store
+            .insert_courses(Language::Zh, &courses, second)
+            .unwrap();
+        store.insert_details(Language::Zh, &details, first).unwrap();
+
+        assert_eq!(
+            store.snapshots(semester, Language::Zh).unwrap(),
+            vec![first, second]
+        );
+        assert!(store.snapshots(semester, Language::En).unwrap().is_empty());
+
+        let latest = store.latest_courses(semester, Language::Zh).unwrap();
+        assert_eq!(latest.len(), 3);
+        assert!(latest.contains(&courses[0]));
+
+        let history = store
+            .course_history(
+                semester,
+                &courses[0].course_no.parse().unwrap(),
+                Language::Zh,
+            )
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].1.choose_student, history[0].1.choose_student + 1);
+
+        let stored = store
+            .latest_details(semester, &course_no, Language::Zh)
+            .unwrap()
+            .unwrap();
+        assert!(details.contains(&stored));
+
+        let count: i64 = store.with_connection(|conn| {
+            conn.query_row("SELECT COUNT(*) FROM course_info", [], |row| row.get(0))
+                .unwrap()
+        });
+        assert_eq!(count, 6);
+    }
+
+    #[test]
+    fn duplicates() {
+        let store = Store::open_in_memory().unwrap();
+        let (courses, details) = fixtures();
+        let now = SystemTime::now();
+
+        // the API returns one entry per time slot group
+        let mut group = courses[0].clone();
+        group.node = Some(String::from("R1"));
+        let mut other = courses[0].clone();
+        other.node = Some(String::from("M2"));
+        assert_eq!(
+            store
+                .insert_courses(Language::Zh, &[group, other], now)
+                .unwrap(),
+            1
+        );
+        let stored = store
+            .latest_courses("1131".parse().unwrap(), Language::Zh)
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].node.as_deref(), Some("M2,R1"));
+
+        let twice = [details[0].clone(), details[0].clone()];
+        assert_eq!(store.insert_details(Language::Zh, &twice, now).unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn source() {
+        let store = Store::open_in_memory().unwrap();
+        let (courses, details) = fixtures();
+        store
+            .insert_courses(Language::Zh, &courses, SystemTime::now())
+            .unwrap();
+        store
+            .insert_details(Language::Zh, &details, SystemTime::now())
+            .unwrap();
+
+        let mut options = SearchOptions::new("1131", Language::Zh);
+        options.course_no = String::from("cs");
+        assert_eq!(
+            CourseSource::search(&store, &options, true)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let semester = "1131".parse().unwrap();
+        let found = CourseSource::query(
+            &store,
+            semester,
+            &"CS2006301".parse().unwrap(),
+            Language::Zh,
+        )
+        .await;
+        assert_eq!(found.unwrap().course_no, "CS2006301");
+        let missing = CourseSource::query(
+            &store,
+            semester,
+            &"CS2006301".parse().unwrap(),
+            Language::En,
+        )
+        .await;
+        assert!(missing.unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn enrollment() {
+        let store = Store::open_in_memory().unwrap();
+        let (_, details) = fixtures();
+        let first = UNIX_EPOCH + Duration::from_secs(1_726_000_000);
+
+        let samples: Vec<Sample> = details
+            .iter()
+            .map(|d| Sample::new(d, None, first))
+            .collect();
+        store.append(&samples).unwrap();
+        store
+            .append(&[Sample::new(
+                &details[0],
+                None,
+                first + Duration::from_secs(60),
+            )])
+            .unwrap();
+
+        let stored = store.samples().unwrap();
+        assert_eq!(stored.len(), samples.len() + 1);
+        assert_eq!(&stored[..samples.len()], &samples[..]);
+    }
+}
@@ -0,0 +1,472 @@
+//! A local stand-in for the NTUST course API, for tests that must not touch the network.
+//!
+//! ```no_run
+//! # async fn example() {
+//! use q::{testing::{Fault, MockServer}, ClientBuilder, Language};
+//!
+//! let server = MockServer::start();
+//! let client = ClientBuilder::new().api_url(server.url()).build();
+//!
+//! let details = client.query("1131", "CS1003302", Language::Zh).await.unwrap();
+//!
+//! server.inject(Fault::Status(503));
+//! assert!(client.query("1131", "CS1003302", Language::Zh).await.is_err());
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+};
+use url::Url;
+
+/// Bundled `courses` responses, an object of `zh` and `en` arrays in the API's own format.
+pub const COURSES_FIXTURE: &str = include_str!("fixtures/courses.json");
+/// Bundled `coursedetials` responses, in the same layout as [`COURSES_FIXTURE`].
+pub const DETAILS_FIXTURE: &str = include_str!("fixtures/details.json");
+
+const API_PATH: &str = "/querycourse/api/";
+const MALFORMED_BODY: &str = r#"[{"Semester": "1131", "CourseNo": "#;
+
+/// A failure applied to a single upcoming request.
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Respond with this status code and a plain text body.
+    Status(u16),
+    /// Respond with `200 OK` and a truncated JSON body.
+    Malformed,
+    /// Wait this long before handling the request normally.
+    Delay(Duration),
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // language -> records
+    courses: HashMap<String, Vec<Value>>,
+    details: HashMap<String, Vec<Value>>,
+    latency: Duration,
+    faults: VecDeque<Fault>,
+    // course number -> faults of the requests about it
+    course_faults: HashMap<String, VecDeque<Fault>>,
+    hits: usize,
+}
+
+/// An HTTP server implementing `courses` and `coursedetials` from fixture JSON.
+///
+/// The server runs on its own thread and runtime, so it serves both the async
+/// and the blocking client. It stops when dropped.
+pub struct MockServer {
+    url: Url,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Start a server with the bundled fixtures.
+    pub fn start() -> Self {
+        Self::with_fixtures(COURSES_FIXTURE, DETAILS_FIXTURE)
+    }
+
+    /// Start a server with custom fixtures, see [`COURSES_FIXTURE`] for the layout.
+    ///
+    /// Panics if the fixtures are not valid.
+    pub fn with_fixtures(courses: &str, details: &str) -> Self {
+        let state = Arc::new(Mutex::new(State {
+            courses: serde_json::from_str(courses).expect("invalid courses fixture"),
+            details: serde_json::from_str(details).expect("invalid details fixture"),
+            ..Default::default()
+        }));
+
+        let listener =
+            std::net::TcpListener::bind("127.0.0.1:0").expect("failed to bind mock server");
+        listener.set_nonblocking(true).unwrap();
+        let url = Url::parse(&format!(
+            "http://{}{}",
+            listener.local_addr().unwrap(),
+            API_PATH
+        ))
+        .unwrap();
+
+        let (shutdown, mut stopped) = oneshot::channel();
+        let server_state = state.clone();
+
+        let thread = thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(async move {
+                let listener = TcpListener::from_std(listener).unwrap();
+                loop {
+                    tokio::select! {
+                        _ = &mut stopped => break,
+                        accepted = listener.accept() => {
+                            if let Ok((socket, _)) = accepted {
+                                tokio::spawn(handle(socket, server_state.clone()));
+                            }
+                        }
+                    }
+                }
+            });
+        });
+
+        MockServer {
+            url,
+            state,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        }
+    }
+
+    /// Base URL to pass to `ClientBuilder::api_url`.
+    pub fn url(&self) -> Url {
+        self.url.clone()
+    }
+
+    /// Delay every response by `latency`.
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().unwrap().latency = latency;
+    }
+
+    /// Queue a fault, faults are applied one per request in the order they were injected.
+    pub fn inject(&self, fault: Fault) {
+        self.state.lock().unwrap().faults.push_back(fault);
+    }
+
+    /// Queue a fault for requests about `course_no` only, these go before the faults of `inject`.
+    ///
+    /// A search is about the course number it filters on, a detail query about the course it asks for.
+    pub fn inject_for(&self, course_no: &str, fault: Fault) {
+        self.state
+            .lock()
+            .unwrap()
+            .course_faults
+            .entry(course_no.to_ascii_uppercase())
+            .or_default()
+            .push_back(fault);
+    }
+
+    /// Edit every record of `course_no` in both fixtures and languages, e.g. to change
+    /// enrollment counts between two polls.
+    pub fn update<F: FnMut(&mut Value)>(&self, course_no: &str, mut f: F) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        state
+            .courses
+            .values_mut()
+            .chain(state.details.values_mut())
+            .flatten()
+            .filter(|record| record["CourseNo"] == course_no)
+            .for_each(&mut f);
+    }
+
+    /// Number of requests received so far.
+    pub fn hits(&self) -> usize {
+        self.state.lock().unwrap().hits
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Request {
+    method: String,
+    target: String,
+    body: Vec<u8>,
+}
+
+async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    loop {
+        let n = socket.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+
+        let head = String::from_utf8_lossy(&buf[..end]).into_owned();
+        let length = head
+            .lines()
+            .find_map(|l| {
+                l.to_ascii_lowercase()
+                    .strip_prefix("content-length:")
+                    .and_then(|v| v.trim().parse::<usize>().ok())
+            })
+            .unwrap_or(0);
+
+        if buf.len() >= end + 4 + length {
+            let mut request_line = head.lines().next()?.split(' ');
+            return Some(Request {
+                method: request_line.next()?.to_string(),
+                target: request_line.next()?.to_string(),
+                body: buf[end + 4..end + 4 + length].to_vec(),
+            });
+        }
+    }
+}
+
+async fn handle(mut socket: TcpStream, state: Arc<Mutex<State>>) {
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+
+    let (latency, fault) = {
+        let mut state = state.lock().unwrap();
+        state.hits += 1;
+        let course_fault = course_no(&request)
+            .and_then(|course_no| state.course_faults.get_mut(&course_no)?.pop_front());
+        (
+            state.latency,
+            course_fault.or_else(|| state.faults.pop_front()),
+        )
+    };
+
+    tokio::time::sleep(latency).await;
+
+    let (status, body) = match fault {
+        Some(Fault::Status(status)) => (status, String::from("mock server error")),
+        Some(Fault::Malformed) => (200, String::from(MALFORMED_BODY)),
+        Some(Fault::Delay(delay)) => {
+            tokio::time::sleep(delay).await;
+            respond(&request, &state.lock().unwrap())
+        }
+        None => respond(&request, &state.lock().unwrap()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+fn request_url(request: &Request) -> Option<Url> {
+    Url::parse("http://localhost")
+        .and_then(|base| base.join(&request.target))
+        .ok()
+}
+
+/// Course number a request is about, uppercased, see `MockServer::inject_for`.
+fn course_no(request: &Request) -> Option<String> {
+    let from_query = request_url(request)?
+        .query_pairs()
+        .find(|(name, _)| name == "course_no")
+        .map(|(_, value)| value.into_owned());
+    let course_no = match from_query {
+        Some(course_no) => course_no,
+        None => serde_json::from_slice::<Value>(&request.body).ok()?["courseNo"]
+            .as_str()?
+            .to_string(),
+    };
+    Some(course_no.to_ascii_uppercase())
+}
+
+fn respond(request: &Request, state: &State) -> (u16, String) {
+    let Some(url) = request_url(request) else {
+        return (400, String::from("bad request target"));
+    };
+
+    let endpoint = url.path().strip_prefix(API_PATH).unwrap_or_default();
+
+    let records: Vec<&Value> = match (request.method.as_str(), endpoint) {
+        ("POST", "courses") => {
+            let Ok(options) = serde_json::from_slice::<Value>(&request.body) else {
+                return (400, String::from("invalid search options"));
+            };
+            let field = |name: &str| options[name].as_str().unwrap_or_default().to_string();
+
+            let semester = field("semester");
+            let course_no = field("courseNo").to_ascii_uppercase();
+            let course_name = field("courseName");
+            let course_teacher = field("courseTeacher");
+
+            state
+                .courses
+                .get(&field("language"))
+                .into_iter()
+                .flatten()
+                .filter(|c| c["Semester"] == semester.as_str())
+                .filter(|c| text(c, "CourseNo").starts_with(&course_no))
+                .filter(|c| text(c, "CourseName").contains(&course_name))
+                .filter(|c| text(c, "CourseTeacher").contains(&course_teacher))
+                .collect()
+        }
+        ("GET", "coursedetials") => {
+            let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+            let param = |name: &str| params.get(name).map(String::as_str).unwrap_or_default();
+
+            state
+                .details
+                .get(param("language"))
+                .into_iter()
+                .flatten()
+                .filter(|c| c["Semester"] == param("semester"))
+                .filter(|c| c["CourseNo"] == param("course_no"))
+                .collect()
+        }
+        _ => return (404, String::from("not found")),
+    };
+
+    (200, serde_json::to_string(&records).unwrap())
+}
+
+fn text<'a>(record: &'a Value, field: &str) -> &'a str {
+    record[field].as_str().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blocking, ClientBuilder, Language, QueryError, RetryPolicy, SearchOptions};
+
+    #[tokio::test]
+    async fn search_and_query() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        let mut options = SearchOptions::new("1131", Language::Zh);
+        options.course_no = String::from("cs");
+        let courses = client.search(&options, true).await.unwrap();
+        assert_eq!(courses.len(), 2);
+        assert!(courses.iter().all(|c| c.course_no.starts_with("CS")));
+
+        let details = client
+            .query("1131", "CS1003302", Language::En)
+            .await
+            .unwrap();
+        assert_eq!(details.course_name, "Computer Programming");
+        assert_eq!(details.limit(), 60);
+
+        let e = client
+            .query("1131", "EE1003302", Language::Zh)
+            .await
+            .unwrap_err();
+        assert!(e.is_not_found());
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn faults() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new()
+            .api_url(server.url())
+            .reqwest_client(
+                reqwest::Client::builder()
+                    .timeout(Duration::from_millis(200))
+                    .build()
+                    .unwrap(),
+            )
+            .build();
+
+        server.inject(Fault::Status(502));
+        let e = client
+            .query("1131", "CS1003302", Language::Zh)
+            .await
+            .unwrap_err();
+        assert_eq!(e.status().map(|s| s.as_u16()), Some(502));
+
+        server.inject(Fault::Malformed);
+        let e = client
+            .query("1131", "CS1003302", Language::Zh)
+            .await
+            .unwrap_err();
+        assert!(matches!(e, QueryError::ParseError { .. }));
+
+        server.inject(Fault::Delay(Duration::from_secs(1)));
+        let e = client
+            .query("1131", "CS1003302", Language::Zh)
+            .await
+            .unwrap_err();
+        assert!(e.is_timeout());
+
+        // faults are consumed, the next request succeeds
+        assert!(client
+            .query("1131", "CS1003302", Language::Zh)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn retry_through_faults() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new()
+            .api_url(server.url())
+            .retry_policy(
+                RetryPolicy::new()
+                    .max_attempts(3)
+                    .initial_backoff(Duration::from_millis(1)),
+            )
+            .build();
+
+        server.inject(Fault::Status(503));
+        server.inject(Fault::Status(500));
+        assert!(client
+            .query("1131", "CS1003302", Language::Zh)
+            .await
+            .is_ok());
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn course_faults() {
+        let server = MockServer::start();
+        let client = ClientBuilder::new().api_url(server.url()).build();
+
+        server.inject_for("cs2006301", Fault::Status(500));
+        server.inject(Fault::Status(502));
+        let (a, b) = tokio::join!(
+            client.query("1131", "CS1003302", Language::Zh),
+            client.query("1131", "CS2006301", Language::Zh),
+        );
+        assert_eq!(a.unwrap_err().status().map(|s| s.as_u16()), Some(502));
+        assert_eq!(b.unwrap_err().status().map(|s| s.as_u16()), Some(500));
+
+        server.update("CS1003302", |record| {
+            record["ChooseStudent"] = Value::from("60");
+        });
+        let details = client
+            .query("1131", "CS1003302", Language::Zh)
+            .await
+            .unwrap();
+        assert!(details.is_full());
+    }
+
+    #[test]
+    fn blocking_client() {
+        let server = MockServer::start();
+        server.set_latency(Duration::from_millis(10));
+        let client = blocking::ClientBuilder::new().api_url(server.url()).build();
+
+        let details = client.query("1132", "CS1003302", Language::Zh).unwrap();
+        assert_eq!(details.choose_student, 12);
+        assert!(!details.is_full());
+    }
+}
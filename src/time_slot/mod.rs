@@ -0,0 +1,289 @@
+use std::{fmt, str::FromStr};
+
+use crate::QueryError;
+
+/// Day of the week, encoded by NTUST as a single letter (`M`, `T`, `W`, `R`, `F`, `S`, `U`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ];
+
+    pub fn code(&self) -> char {
+        match self {
+            Weekday::Mon => 'M',
+            Weekday::Tue => 'T',
+            Weekday::Wed => 'W',
+            Weekday::Thu => 'R',
+            Weekday::Fri => 'F',
+            Weekday::Sat => 'S',
+            Weekday::Sun => 'U',
+        }
+    }
+
+    pub fn from_code(code: char) -> Option<Self> {
+        match code.to_ascii_uppercase() {
+            'M' => Some(Weekday::Mon),
+            'T' => Some(Weekday::Tue),
+            'W' => Some(Weekday::Wed),
+            'R' => Some(Weekday::Thu),
+            'F' => Some(Weekday::Fri),
+            'S' => Some(Weekday::Sat),
+            'U' => Some(Weekday::Sun),
+            _ => None,
+        }
+    }
+
+    /// Zero-based index, Monday first.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// Class period of a day: `1`–`10` during the day and `A`–`D` in the evening.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Period {
+    P1,
+    P2,
+    P3,
+    P4,
+    P5,
+    P6,
+    P7,
+    P8,
+    P9,
+    P10,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Period {
+    pub const ALL: [Period; 14] = [
+        Period::P1,
+        Period::P2,
+        Period::P3,
+        Period::P4,
+        Period::P5,
+        Period::P6,
+        Period::P7,
+        Period::P8,
+        Period::P9,
+        Period::P10,
+        Period::A,
+        Period::B,
+        Period::C,
+        Period::D,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::P1 => "1",
+            Period::P2 => "2",
+            Period::P3 => "3",
+            Period::P4 => "4",
+            Period::P5 => "5",
+            Period::P6 => "6",
+            Period::P7 => "7",
+            Period::P8 => "8",
+            Period::P9 => "9",
+            Period::P10 => "10",
+            Period::A => "A",
+            Period::B => "B",
+            Period::C => "C",
+            Period::D => "D",
+        }
+    }
+
+    /// Zero-based index in chronological order.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Start time of the period as `(hour, minute)`.
+    pub fn start_time(&self) -> (u8, u8) {
+        match self {
+            Period::P1 => (8, 10),
+            Period::P2 => (9, 10),
+            Period::P3 => (10, 20),
+            Period::P4 => (11, 20),
+            Period::P5 => (12, 20),
+            Period::P6 => (13, 20),
+            Period::P7 => (14, 20),
+            Period::P8 => (15, 30),
+            Period::P9 => (16, 30),
+            Period::P10 => (17, 30),
+            Period::A => (18, 25),
+            Period::B => (19, 20),
+            Period::C => (20, 15),
+            Period::D => (21, 10),
+        }
+    }
+
+    /// End time of the period as `(hour, minute)`.
+    pub fn end_time(&self) -> (u8, u8) {
+        match self {
+            Period::P1 => (9, 0),
+            Period::P2 => (10, 0),
+            Period::P3 => (11, 10),
+            Period::P4 => (12, 10),
+            Period::P5 => (13, 10),
+            Period::P6 => (14, 10),
+            Period::P7 => (15, 10),
+            Period::P8 => (16, 20),
+            Period::P9 => (17, 20),
+            Period::P10 => (18, 20),
+            Period::A => (19, 15),
+            Period::B => (20, 10),
+            Period::C => (21, 5),
+            Period::D => (22, 0),
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<Period, Self::Err> {
+        Period::ALL
+            .into_iter()
+            .find(|p| p.as_str().eq_ignore_ascii_case(input))
+            .ok_or_else(|| QueryError::InputError(format!("invalid period: {:?}", input)))
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single class period on a given weekday, e.g. `M1` or `R10`.
+///
+/// Ordering is chronological: by weekday first, then by period.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeSlot {
+    pub weekday: Weekday,
+    pub period: Period,
+}
+
+impl TimeSlot {
+    pub fn new(weekday: Weekday, period: Period) -> Self {
+        Self { weekday, period }
+    }
+
+    /// Parse a comma-joined node list such as `"M1,M2,T3"`, skipping empty entries.
+    pub fn parse_list(input: &str) -> Result<Vec<TimeSlot>, QueryError> {
+        input
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(TimeSlot::from_str)
+            .collect()
+    }
+}
+
+impl FromStr for TimeSlot {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<TimeSlot, Self::Err> {
+        let mut chars = input.chars();
+
+        let weekday = chars
+            .next()
+            .and_then(Weekday::from_code)
+            .ok_or_else(|| QueryError::InputError(format!("invalid time slot: {:?}", input)))?;
+
+        let period = chars
+            .as_str()
+            .parse::<Period>()
+            .map_err(|_| QueryError::InputError(format!("invalid time slot: {:?}", input)))?;
+
+        Ok(TimeSlot { weekday, period })
+    }
+}
+
+impl fmt::Display for TimeSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.weekday, self.period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "M1".parse::<TimeSlot>().unwrap(),
+            TimeSlot::new(Weekday::Mon, Period::P1)
+        );
+        assert_eq!(
+            "R10".parse::<TimeSlot>().unwrap(),
+            TimeSlot::new(Weekday::Thu, Period::P10)
+        );
+        assert_eq!(
+            "uc".parse::<TimeSlot>().unwrap(),
+            TimeSlot::new(Weekday::Sun, Period::C)
+        );
+
+        assert!("".parse::<TimeSlot>().is_err());
+        assert!("X1".parse::<TimeSlot>().is_err());
+        assert!("M0".parse::<TimeSlot>().is_err());
+        assert!("M11".parse::<TimeSlot>().is_err());
+        assert!("ME".parse::<TimeSlot>().is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for weekday in Weekday::ALL {
+            for period in Period::ALL {
+                let slot = TimeSlot::new(weekday, period);
+                assert_eq!(slot.to_string().parse::<TimeSlot>().unwrap(), slot);
+            }
+        }
+    }
+
+    #[test]
+    fn clock_times() {
+        for pair in Period::ALL.windows(2) {
+            assert!(pair[0].start_time() < pair[0].end_time());
+            assert!(pair[0].end_time() <= pair[1].start_time());
+        }
+        assert_eq!(Period::P1.start_time(), (8, 10));
+        assert_eq!(Period::D.end_time(), (22, 0));
+    }
+
+    #[test]
+    fn chronological_order() {
+        let mut slots = TimeSlot::parse_list("T1,M10,MA,M2,R3").unwrap();
+        slots.sort();
+
+        let slots: Vec<String> = slots.iter().map(|s| s.to_string()).collect();
+        assert_eq!(slots, vec!["M2", "M10", "MA", "T1", "R3"]);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{CourseInfo, Period, QueryError, TimeSlot, Weekday};
+
+/// Two courses sharing at least one time slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub first: String,
+    pub second: String,
+    pub slots: Vec<TimeSlot>,
+}
+
+/// Courses laid out by weekday and period, indexed as `grid[weekday.index()][period.index()]`.
+pub type Grid<'a> = [[Vec<&'a CourseInfo>; Period::ALL.len()]; Weekday::ALL.len()];
+
+#[derive(Debug, Clone, Default)]
+pub struct Timetable {
+    entries: Vec<(CourseInfo, Vec<TimeSlot>)>,
+}
+
+impl Timetable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_courses<I>(courses: I) -> Result<Self, QueryError>
+    where
+        I: IntoIterator<Item = CourseInfo>,
+    {
+        let mut timetable = Self::new();
+        for course in courses {
+            timetable.add(course)?;
+        }
+        Ok(timetable)
+    }
+
+    /// Add a course, merging its slots into an existing entry with the same `course_no`.
+    pub fn add(&mut self, course: CourseInfo) -> Result<(), QueryError> {
+        let slots = course.time_slots()?;
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|(c, _)| c.course_no == course.course_no)
+        {
+            Some((_, existing)) => {
+                existing.extend(slots);
+                existing.sort();
+                existing.dedup();
+            }
+            None => self.entries.push((course, slots)),
+        }
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, course_no: &str) -> Option<CourseInfo> {
+        let index = self
+            .entries
+            .iter()
+            .position(|(c, _)| c.course_no == course_no)?;
+        Some(self.entries.remove(index).0)
+    }
+
+    pub fn courses(&self) -> impl Iterator<Item = &CourseInfo> {
+        self.entries.iter().map(|(c, _)| c)
+    }
+
+    pub fn slots(&self, course_no: &str) -> Option<&[TimeSlot]> {
+        self.entries
+            .iter()
+            .find(|(c, _)| c.course_no == course_no)
+            .map(|(_, slots)| slots.as_slice())
+    }
+
+    /// Every pair of courses whose slots overlap, in insertion order.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+
+        for (i, (a, a_slots)) in self.entries.iter().enumerate() {
+            for (b, b_slots) in &self.entries[i + 1..] {
+                let slots = overlap(a_slots, b_slots);
+                if !slots.is_empty() {
+                    conflicts.push(Conflict {
+                        first: a.course_no.clone(),
+                        second: b.course_no.clone(),
+                        slots,
+                    });
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// Conflicts the given course would introduce, without adding it.
+    pub fn conflicts_with(&self, course: &CourseInfo) -> Result<Vec<Conflict>, QueryError> {
+        let slots = course.time_slots()?;
+
+        Ok(self
+            .entries
+            .iter()
+            .filter(|(c, _)| c.course_no != course.course_no)
+            .filter_map(|(c, c_slots)| {
+                let overlapping = overlap(c_slots, &slots);
+                (!overlapping.is_empty()).then(|| Conflict {
+                    first: c.course_no.clone(),
+                    second: course.course_no.clone(),
+                    slots: overlapping,
+                })
+            })
+            .collect())
+    }
+
+    pub fn at(&self, slot: TimeSlot) -> Vec<&CourseInfo> {
+        self.entries
+            .iter()
+            .filter(|(_, slots)| slots.binary_search(&slot).is_ok())
+            .map(|(c, _)| c)
+            .collect()
+    }
+
+    pub fn grid(&self) -> Grid<'_> {
+        let mut grid: Grid<'_> = Default::default();
+
+        for (course, slots) in &self.entries {
+            for slot in slots {
+                grid[slot.weekday.index()][slot.period.index()].push(course);
+            }
+        }
+
+        grid
+    }
+}
+
+// both inputs are sorted
+fn overlap(a: &[TimeSlot], b: &[TimeSlot]) -> Vec<TimeSlot> {
+    a.iter()
+        .filter(|slot| b.binary_search(slot).is_ok())
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course(course_no: &str, node: &str) -> CourseInfo {
+        CourseInfo {
+            semester: String::from("1131"),
+            course_no: String::from(course_no),
+            node: Some(String::from(node)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn conflicts() {
+        let timetable = Timetable::from_courses(vec![
+            course("CS1003302", "M1,M2"),
+            course("CS2006302", "M2,M3,T5"),
+            course("GE3729302", "T5"),
+            course("PE111B022", "F9"),
+        ])
+        .unwrap();
+
+        let conflicts = timetable.conflicts();
+
+        assert_eq!(
+            conflicts,
+            vec![
+                Conflict {
+                    first: String::from("CS1003302"),
+                    second: String::from("CS2006302"),
+                    slots: vec!["M2".parse().unwrap()],
+                },
+                Conflict {
+                    first: String::from("CS2006302"),
+                    second: String::from("GE3729302"),
+                    slots: vec!["T5".parse().unwrap()],
+                },
+            ]
+        );
+
+        let candidate = course("TCG046301", "F9,FA");
+        let conflicts = timetable.conflicts_with(&candidate).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].first, "PE111B022");
+    }
+
+    #[test]
+    fn merge_split_entries() {
+        let mut timetable = Timetable::new();
+        timetable.add(course("CS1003302", "R1")).unwrap();
+        timetable.add(course("CS1003302", "T1,T2")).unwrap();
+
+        assert_eq!(timetable.courses().count(), 1);
+        assert_eq!(
+            timetable.slots("CS1003302").unwrap(),
+            TimeSlot::parse_list("T1,T2,R1").unwrap()
+        );
+        assert!(timetable.conflicts().is_empty());
+    }
+
+    #[test]
+    fn grid() {
+        let timetable = Timetable::from_courses(vec![
+            course("CS1003302", "M1"),
+            course("CS2006302", "M1,WA"),
+        ])
+        .unwrap();
+
+        let grid = timetable.grid();
+
+        assert_eq!(grid[Weekday::Mon.index()][Period::P1.index()].len(), 2);
+        assert_eq!(
+            grid[Weekday::Wed.index()][Period::A.index()][0].course_no,
+            "CS2006302"
+        );
+        assert!(grid[Weekday::Tue.index()][Period::P1.index()].is_empty());
+        assert_eq!(
+            timetable.at("WA".parse().unwrap())[0].course_no,
+            "CS2006302"
+        );
+    }
+
+    #[test]
+    fn invalid_node() {
+        let mut timetable = Timetable::new();
+        assert!(timetable.add(course("CS1003302", "X1")).is_err());
+        assert_eq!(timetable.courses().count(), 0);
+    }
+}